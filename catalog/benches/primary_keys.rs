@@ -0,0 +1,34 @@
+use catalog::catalog::Catalog;
+use catalog::lookup::{ExtraValue, InternalId};
+use criterion::{black_box, criterion_group, criterion_main, Criterion};
+
+/// Catalog with as many bundles as a large game Catalog has entries
+fn large_catalog() -> (Catalog, Vec<InternalId>) {
+    let mut catalog = Catalog::default();
+
+    for index in 0..5000 {
+        let name = format!("fe_assets_unit/model/ubody/{0}/prefabs/ubody_{0}.bundle", index);
+        catalog.add_bundle(name.as_str(), name.as_str(), ExtraValue::default()).unwrap();
+    }
+
+    let ids = (0..5000).map(InternalId).collect();
+
+    (catalog, ids)
+}
+
+fn primary_keys(c: &mut Criterion) {
+    let (catalog, ids) = large_catalog();
+
+    c.bench_function("primary keys one by one", |b| {
+        b.iter(|| {
+            ids.iter()
+            .map(|id| catalog.get_entry_by_internal_id(*id).and_then(|entry| catalog.primary_key_string(entry)))
+            .collect::<Vec<_>>()
+        })
+    });
+
+    c.bench_function("primary keys in a batch", |b| b.iter(|| catalog.resolve_primary_keys(black_box(&ids))));
+}
+
+criterion_group!(benches, primary_keys);
+criterion_main!(benches);
@@ -0,0 +1,78 @@
+use std::alloc::{GlobalAlloc, Layout, System};
+use std::sync::atomic::{AtomicUsize, Ordering};
+
+use catalog::catalog::Catalog;
+use catalog::lookup::ExtraValue;
+use criterion::{criterion_group, Criterion};
+
+/// Allocator keeping track of the peak amount of memory in use, to compare the write paths
+struct PeakAllocator;
+
+static CURRENT: AtomicUsize = AtomicUsize::new(0);
+static PEAK: AtomicUsize = AtomicUsize::new(0);
+
+unsafe impl GlobalAlloc for PeakAllocator {
+    unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+        let ptr = System.alloc(layout);
+
+        if !ptr.is_null() {
+            let current = CURRENT.fetch_add(layout.size(), Ordering::Relaxed) + layout.size();
+            PEAK.fetch_max(current, Ordering::Relaxed);
+        }
+
+        ptr
+    }
+
+    unsafe fn dealloc(&self, ptr: *mut u8, layout: Layout) {
+        System.dealloc(ptr, layout);
+        CURRENT.fetch_sub(layout.size(), Ordering::Relaxed);
+    }
+}
+
+#[global_allocator]
+static ALLOCATOR: PeakAllocator = PeakAllocator;
+
+/// Catalog with as many prefabs and bundles as a large game Catalog has entries
+fn large_catalog() -> Catalog {
+    let mut catalog = Catalog::default();
+    catalog.set_hash_seed(0);
+
+    for index in 0..5000 {
+        let bundle = format!("{{UnityEngine.AddressableAssets.Addressables.RuntimePath}}/Switch/fe_assets_unit/model/ubody/{0}/prefabs/ubody_{0}.bundle", index);
+        catalog.add_bundle(bundle.as_str(), &bundle[57..], ExtraValue::default()).unwrap();
+        catalog.add_prefab(format!("Assets/Unit/uBody_{}.prefab", index), format!("Unit/uBody_{}", index), &[bundle]).unwrap();
+    }
+
+    catalog
+}
+
+/// Peak memory used by a write on top of what was already allocated
+fn peak_of<F: FnOnce()>(write: F) -> usize {
+    let before = CURRENT.load(Ordering::Relaxed);
+    PEAK.store(before, Ordering::Relaxed);
+
+    write();
+
+    PEAK.load(Ordering::Relaxed) - before
+}
+
+fn write_catalog(c: &mut Criterion) {
+    let catalog = large_catalog();
+
+    let string_peak = peak_of(|| std::io::Write::write_all(&mut std::io::sink(), catalog.to_json(false).unwrap().as_bytes()).unwrap());
+    let stream_peak = peak_of(|| catalog.write_to(std::io::sink(), false).unwrap());
+    println!("Peak memory: {} bytes building the string, {} bytes streaming", string_peak, stream_peak);
+
+    c.bench_function("write through a string", |b| {
+        b.iter(|| std::io::Write::write_all(&mut std::io::sink(), catalog.to_json(false).unwrap().as_bytes()).unwrap())
+    });
+
+    c.bench_function("write to a stream", |b| b.iter(|| catalog.write_to(std::io::sink(), false).unwrap()));
+}
+
+criterion_group!(benches, write_catalog);
+
+fn main() {
+    benches();
+    Criterion::default().configure_from_args().final_summary();
+}
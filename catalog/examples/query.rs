@@ -0,0 +1,54 @@
+//! Read-only queries on a Catalog, using only the public API of the crate.
+//!
+//! ```sh
+//! cargo run --example query -- catalog.json "Assets/Unit/uBody_A.prefab"
+//! ```
+
+use catalog::catalog::Catalog;
+
+fn main() -> Result<(), Box<dyn std::error::Error>> {
+    let mut args = std::env::args().skip(1);
+    let path = args.next().ok_or("usage: query <catalog> [internal id]")?;
+
+    let catalog = Catalog::open(path)?;
+    println!("{}", catalog);
+
+    // Every entry, with whether it is a bundle or loaded from one
+    for (index, entry) in catalog.get_entries().iter().enumerate() {
+        let internal_id = catalog.get_internal_id_from_index(entry.internal_id).unwrap_or_default();
+        let kind = if entry.dependency_hash == 0 { "bundle" } else { "asset" };
+
+        println!("{:>6} {:<6} {}", index, kind, internal_id);
+    }
+
+    let query = match args.next() {
+        Some(query) => query,
+        None => return Ok(()),
+    };
+
+    let internal_id = catalog.get_internal_id_index(&query).ok_or("no such InternalId")?;
+    let entry_id = catalog.entry_id_of(internal_id).ok_or("the InternalId has no entry")?;
+    let summary = catalog.entry_summary(entry_id).ok_or("the entry is corrupted")?;
+
+    println!();
+    println!("{}", summary.internal_id);
+    println!("  primary key: {}", summary.primary_key.as_deref().unwrap_or("<hash>"));
+    println!("  provider: {}", summary.provider.as_deref().unwrap_or("<unknown>"));
+    println!("  resource type: {}", summary.resource_type.as_deref().unwrap_or("<unknown>"));
+
+    // Everything needed to load the entry, each dependency only once
+    let entry = catalog.get_entry(entry_id).ok_or("the entry is corrupted")?;
+
+    for dependency in catalog.dependencies_recursive(entry) {
+        let name = catalog
+            .get_entry(dependency)
+            .and_then(|dependency| catalog.get_internal_id_from_index(dependency.internal_id))
+            .unwrap_or_default();
+
+        println!("  depends on {}", name);
+    }
+
+    println!("  {} bytes of bundles in total", catalog.recursive_size(entry));
+
+    Ok(())
+}
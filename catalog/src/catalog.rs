@@ -1,24 +1,68 @@
+use std::borrow::Cow;
+use std::collections::{HashMap, HashSet};
+#[cfg(feature = "json")]
+use std::io::{Read, Write};
+#[cfg(feature = "json")]
+use std::path::Path;
+
+#[cfg(feature = "json")]
+use astra_formats::TextBundle;
 use binrw::{BinResult, BinRead, BinWrite, meta::WriteEndian};
 use serde::{Deserialize, Serialize, Deserializer, Serializer};
 use thiserror::Error;
 use base64;
+#[cfg(feature = "edit")]
+use log::{debug, trace, warn};
 
-use rand::{self, Rng};
+#[cfg(feature = "edit")]
+use rand::{self, rngs::StdRng, Rng, SeedableRng};
 
 use crate::lookup::{InternalId, KeyData, BucketData, EntryData, ExtraData, KeyId, KeyDataValue, BucketEntry, EntryId, EntryValue, ExtraId, ExtraValue};
 
+/// New variants can be added without it being a breaking change, so matches need a wildcard arm
 #[derive(Debug, Error)]
+#[non_exhaustive]
 pub enum CatalogError {
     #[error("a filesystem error happened: {0}")]
     Io(#[from] std::io::Error),
+    #[cfg(feature = "json")]
     #[error("a json parsing error happened: {0}")]
     Json(#[from] serde_json::Error),
     #[error("a decoding error happened: {0}")]
     Base64Decode(#[from] base64::DecodeError),
-    #[error("a internalid with this string already exists")]
-    DuplicateInternalId,
-    #[error("a internalid with this string does not exist")]
-    MissingInternalId,
+    #[cfg(feature = "json")]
+    #[error("a bundle error happened: {0}")]
+    Bundle(#[from] anyhow::Error),
+    #[error("a internalid with the string '{0}' already exists")]
+    DuplicateInternalId(String),
+    #[error("no entry with the internalid '{0}' exists")]
+    MissingInternalId(String),
+    #[error("a table has more entries than its index type can represent")]
+    TableOverflow,
+    #[error("the key is a hash where a string was expected")]
+    HashKey,
+    #[error("a dependency key with this hash already exists")]
+    DuplicateHash,
+    #[error("no entry with this id exists")]
+    MissingEntry,
+    #[error("the entry has no key or its key does not exist")]
+    MissingKey,
+    #[error("the entry has no extra data")]
+    MissingExtraData,
+    #[error("the bundle is not a dependency of this entry")]
+    NotADependency,
+    #[error("a provider with the id '{0}' already exists")]
+    DuplicateProvider(String),
+    #[error("no provider with the id '{0}' exists")]
+    MissingProvider(String),
+    #[error("unsupported Catalog layout ({0}), only the JSON Catalogs of Addressables 1.x are supported")]
+    UnsupportedVersion(String),
+}
+
+/// Convert a table index or length to the integer type used to store it, instead of silently wrapping around
+#[cfg(feature = "edit")]
+fn checked_index<T: TryFrom<usize>>(index: usize) -> Result<T, CatalogError> {
+    T::try_from(index).map_err(|_| CatalogError::TableOverflow)
 }
 
 fn serialize_catalog_table<T, S>(v: T, serializer: S) -> Result<S::Ok, S::Error>
@@ -42,222 +86,2666 @@ D: Deserializer<'de> {
     T::read_le_args(&mut std::io::Cursor::new(buf), ()).map_err(serde::de::Error::custom)
 }
 
+/// Decode the entry table, refusing the layouts of other Addressables versions instead of misreading them
+fn deserialize_entry_table<'de, D>(deserializer: D) -> Result<EntryData, D::Error>
+where
+D: Deserializer<'de> {
+    let buf = String::deserialize(deserializer)?;
+    let buf = base64::decode(&buf).map_err(CatalogError::Base64Decode).map_err(serde::de::Error::custom)?;
+    check_entry_layout(&buf).map_err(serde::de::Error::custom)?;
+
+    EntryData::read_le_args(&mut std::io::Cursor::new(buf), ()).map_err(serde::de::Error::custom)
+}
+
+fn encode_value<T: BinWrite<Args<'static> = ()>>(value: &T) -> Option<Vec<u8>> {
+    let mut buff = std::io::Cursor::new(Vec::new());
+    value.write_le_args(&mut buff, ()).ok()?;
+    Some(buff.into_inner())
+}
+
+
+/// Size of an entry in the entry table of the Catalog layouts this crate can read
+const ENTRY_SIZE: usize = 7 * 4;
+
+/// The only field needed to tell whether a Catalog has a layout this crate can read
+#[cfg(feature = "json")]
+#[derive(Deserialize)]
+struct CatalogLayout {
+    #[serde(alias = "EntryDataString", alias = "entryDataString")]
+    m_EntryDataString: Option<String>,
+}
+
+/// Check that a JSON Catalog has a layout this crate can read before parsing its tables.
+/// Other Addressables versions do not give the same size to entries, which is used to tell them apart.
+#[cfg(feature = "json")]
+pub fn check_version(json: &[u8]) -> Result<(), CatalogError> {
+    let layout: CatalogLayout = serde_json::from_slice(json)?;
+
+    let table = layout.m_EntryDataString.ok_or_else(|| CatalogError::UnsupportedVersion(String::from("no entry table")))?;
+    check_entry_layout(&base64::decode(table)?)
+}
+
+/// Check that the entries of a decoded entry table have the size this crate expects
+fn check_entry_layout(table: &[u8]) -> Result<(), CatalogError> {
+    let count = table.get(..4).map(|count| i32::from_le_bytes([count[0], count[1], count[2], count[3]]));
+    let entries_size = table.len().saturating_sub(4);
+
+    match count {
+        Some(count) if count >= 0 && entries_size == count as usize * ENTRY_SIZE => Ok(()),
+        Some(count) if count > 0 && entries_size.is_multiple_of(count as usize) => {
+            Err(CatalogError::UnsupportedVersion(format!("entries of {} bytes instead of {}", entries_size / count as usize, ENTRY_SIZE)))
+        },
+        _ => Err(CatalogError::UnsupportedVersion(format!("entry table of {} bytes does not match its entry count", table.len()))),
+    }
+}
+
+/// Magic bytes at the start of a gzip stream
+#[cfg(feature = "json")]
+const GZIP_MAGIC: [u8; 2] = [0x1f, 0x8b];
+
+/// Signatures at the start of the Unity bundle formats
+const BUNDLE_SIGNATURES: [&[u8]; 3] = [b"UnityFS\0", b"UnityWeb\0", b"UnityRaw\0"];
+
+/// Check if the data looks like a Unity bundle rather than a JSON Catalog, compressed or not
+pub fn is_bundle(data: &[u8]) -> bool {
+    BUNDLE_SIGNATURES.iter().any(|signature| data.starts_with(signature))
+}
+
+/// Check if the data looks like a JSON Catalog, by looking for an opening brace at the start of it
+pub fn is_json(data: &[u8]) -> bool {
+    let data = data.strip_prefix(b"\xEF\xBB\xBF").unwrap_or(data);
+    data.iter().find(|byte| !byte.is_ascii_whitespace()) == Some(&b'{')
+}
+
+/// Decompress a Catalog compressed with gzip or brotli. Uncompressed data is returned as is.
+/// Brotli streams do not start with magic bytes, so anything that is neither gzip nor JSON is assumed to be brotli.
+#[cfg(feature = "json")]
+fn decompress(data: &[u8]) -> Result<Cow<'_, [u8]>, CatalogError> {
+    if data.starts_with(&GZIP_MAGIC) {
+        let mut json = Vec::new();
+        flate2::read::GzDecoder::new(data).read_to_end(&mut json)?;
+        Ok(Cow::Owned(json))
+    } else if is_json(data) {
+        Ok(Cow::Borrowed(data))
+    } else {
+        let mut json = Vec::new();
+
+        match brotli::Decompressor::new(data, 4096).read_to_end(&mut json) {
+            Ok(_) => Ok(Cow::Owned(json)),
+            // Not brotli either, let the JSON parser report what is wrong with it
+            Err(_) => Ok(Cow::Borrowed(data)),
+        }
+    }
+}
+
+/// Open a Catalog either from a bundle or from a JSON file
+#[cfg(feature = "json")]
+pub fn load_catalog<P: AsRef<Path>>(path: P, bundled: bool) -> Result<Catalog, CatalogError> {
+    if bundled {
+        Catalog::from_bundle(path)
+    } else {
+        Catalog::open(path)
+    }
+}
+
+/// Read a Catalog from a stream such as stdin, either as a bundle or as a (possibly compressed) JSON.
+/// The stream is read to the end before parsing, since bundles can't be parsed incrementally.
+#[cfg(feature = "json")]
+pub fn read_catalog<R: Read>(mut reader: R, bundled: bool) -> Result<Catalog, CatalogError> {
+    let mut data = Vec::new();
+    reader.read_to_end(&mut data)?;
+
+    if bundled {
+        Catalog::from_bundle_slice(data)
+    } else {
+        Catalog::from_slice(decompress(&data)?)
+    }
+}
 
-#[derive(Deserialize, Serialize)]
+/// Unity writes the fields with their `m_` prefix, but Catalogs re-serialized by other tools
+/// drop it, in either PascalCase or camelCase. Every variant is accepted, and the Unity one is written back.
+///
+/// # Thread safety
+///
+/// A Catalog has no interior mutability, so it is `Send + Sync` and can be shared behind an `Arc`
+/// with its read methods called from several threads at once.
+#[derive(Deserialize, Serialize, Default)]
 pub struct Catalog {
+    #[serde(alias = "LocatorId", alias = "locatorId")]
     m_LocatorId: String,
-    m_InstanceProviderData: ProviderData,
-    m_SceneProviderData: ProviderData,
+    /// Null in some minimal Catalogs
+    #[serde(alias = "InstanceProviderData", alias = "instanceProviderData")]
+    m_InstanceProviderData: Option<ProviderData>,
+    #[serde(alias = "SceneProviderData", alias = "sceneProviderData")]
+    m_SceneProviderData: Option<ProviderData>,
+    #[serde(alias = "ResourceProviderData", alias = "resourceProviderData")]
     m_ResourceProviderData: Vec<ProviderData>,
+    #[serde(alias = "ProviderIds", alias = "providerIds")]
     m_ProviderIds: Vec<String>,
+    #[serde(alias = "InternalIds", alias = "internalIds")]
     pub m_InternalIds: Vec<String>,
+    #[serde(alias = "KeyDataString", alias = "keyDataString")]
     #[serde(deserialize_with = "deserialize_catalog_table", serialize_with = "serialize_catalog_table")]
     pub m_KeyDataString: KeyData,
+    #[serde(alias = "BucketDataString", alias = "bucketDataString")]
     #[serde(deserialize_with = "deserialize_catalog_table", serialize_with = "serialize_catalog_table")]
     m_BucketDataString: BucketData,
-    #[serde(deserialize_with = "deserialize_catalog_table", serialize_with = "serialize_catalog_table")]
+    #[serde(alias = "EntryDataString", alias = "entryDataString")]
+    #[serde(deserialize_with = "deserialize_entry_table", serialize_with = "serialize_catalog_table")]
     m_EntryDataString: EntryData,
+    #[serde(alias = "ExtraDataString", alias = "extraDataString")]
     #[serde(deserialize_with = "deserialize_catalog_table", serialize_with = "serialize_catalog_table")]
     m_ExtraDataString: ExtraData,
+    #[serde(alias = "m_ResourceTypes", alias = "ResourceTypes", alias = "resourceTypes")]
     m_resourceTypes: Vec<ObjectType>,
+    #[serde(alias = "InternalIdPrefixes", alias = "internalIdPrefixes")]
     m_InternalIdPrefixes: Vec<String>,
+    /// Generator for new dependency hashes. Randomly seeded unless set_hash_seed was called
+    #[cfg(feature = "edit")]
+    #[serde(skip)]
+    hash_rng: Option<StdRng>,
+}
+
+impl std::fmt::Display for Catalog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(
+            f,
+            "Catalog '{}': {} InternalIds, {} keys, {} entries, {} bytes of extra data",
+            self.m_LocatorId,
+            self.internal_id_count(),
+            self.key_count(),
+            self.entry_count(),
+            self.extra_data_size(),
+        )
+    }
+}
+
+/// Only reports the size of the tables, as printing them whole is rarely useful
+impl std::fmt::Debug for Catalog {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Catalog")
+        .field("locator_id", &self.m_LocatorId)
+        .field("provider_ids", &self.m_ProviderIds)
+        .field("internal_ids", &self.internal_id_count())
+        .field("internal_id_prefixes", &self.m_InternalIdPrefixes.len())
+        .field("resource_types", &self.m_resourceTypes.len())
+        .field("keys", &self.key_count())
+        .field("buckets", &self.bucket_count())
+        .field("entries", &self.entry_count())
+        .field("extra_data_size", &self.extra_data_size())
+        .finish()
+    }
+}
+
+/// Detects whether the bytes are a bundle or a JSON, see [`Catalog::load_auto`]
+#[cfg(feature = "json")]
+impl TryFrom<&[u8]> for Catalog {
+    type Error = CatalogError;
+
+    fn try_from(bytes: &[u8]) -> Result<Self, Self::Error> {
+        Self::load_auto(bytes)
+    }
+}
+
+/// Compares the content of the Catalogs, ignoring the state of the hash generator
+impl PartialEq for Catalog {
+    fn eq(&self, other: &Self) -> bool {
+        self.m_LocatorId == other.m_LocatorId
+        && self.m_InstanceProviderData == other.m_InstanceProviderData
+        && self.m_SceneProviderData == other.m_SceneProviderData
+        && self.m_ResourceProviderData == other.m_ResourceProviderData
+        && self.m_ProviderIds == other.m_ProviderIds
+        && self.m_InternalIds == other.m_InternalIds
+        && self.m_KeyDataString == other.m_KeyDataString
+        && self.m_BucketDataString == other.m_BucketDataString
+        && self.m_EntryDataString == other.m_EntryDataString
+        && self.m_ExtraDataString == other.m_ExtraDataString
+        && self.m_resourceTypes == other.m_resourceTypes
+        && self.m_InternalIdPrefixes == other.m_InternalIdPrefixes
+    }
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Default, PartialEq)]
 pub struct ProviderData {
+    #[serde(alias = "Id", alias = "id")]
     m_Id: String,
+    #[serde(alias = "ObjectType", alias = "objectType")]
     m_ObjectType: ObjectType,
+    #[serde(alias = "Data", alias = "data")]
     m_Data: String,
 }
 
-#[derive(Deserialize, Serialize)]
+#[derive(Deserialize, Serialize, Default, PartialEq, Eq)]
 pub struct ObjectType {
+    #[serde(alias = "AssemblyName", alias = "assemblyName")]
     m_AssemblyName: String,
+    #[serde(alias = "ClassName", alias = "className")]
     pub m_ClassName: String,
 }
 
+impl ObjectType {
+    pub fn new<S: Into<String>>(assembly_name: S, class_name: S) -> Self {
+        ObjectType { m_AssemblyName: assembly_name.into(), m_ClassName: class_name.into() }
+    }
+
+    pub fn assembly_name(&self) -> &str {
+        &self.m_AssemblyName
+    }
+
+    pub fn class_name(&self) -> &str {
+        &self.m_ClassName
+    }
+}
+
+/// Everything the Catalog knows about an entry, resolved from its indices
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct EntrySummary {
+    pub internal_id: String,
+    /// Class name of the resource type
+    pub resource_type: Option<String>,
+    pub provider: Option<String>,
+    /// None if the primary key is a hash
+    pub primary_key: Option<String>,
+    pub dependencies: Vec<String>,
+    pub is_bundle: bool,
+}
+
+/// Bucket whose key offset does not match where its key starts in the key table
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct KeyOffsetMismatch {
+    pub key: KeyId,
+    pub stored: u32,
+    pub expected: u32,
+}
+
+/// Dependency hash held by several keys whose buckets list different dependencies.
+/// Unity finds dependencies by hash, so all but one of these keys resolve to the wrong bundles.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct HashCollision {
+    pub hash: i32,
+    pub keys: Vec<KeyId>,
+}
+
+#[cfg(feature = "json")]
 impl Catalog {
+    /// Open a JSON Catalog, decompressing it first if it was compressed with gzip or brotli
     pub fn open<P: AsRef<std::path::Path>>(path: P) -> Result<Self, CatalogError> {
-        let catalog_str = &std::fs::read_to_string(path.as_ref())?;
-        serde_json::from_str(catalog_str).map_err(CatalogError::Json)
+        let data = std::fs::read(path.as_ref())?;
+        Self::from_slice(decompress(&data)?)
     }
 
     pub fn from_str<S: AsRef<str>>(string: S) -> Result<Self, CatalogError> {
-        serde_json::from_str(string.as_ref()).map_err(CatalogError::Json)
+        Self::from_slice(string.as_ref())
     }
 
     pub fn from_slice<S: AsRef<[u8]>>(slice: S) -> Result<Self, CatalogError> {
-        serde_json::from_slice(slice.as_ref()).map_err(CatalogError::Json)
+        // The entry table refuses unsupported layouts as it is decoded, so the JSON is only parsed again to explain a failure
+        serde_json::from_slice(slice.as_ref()).map_err(|err| match check_version(slice.as_ref()) {
+            Err(unsupported @ CatalogError::UnsupportedVersion(_)) => unsupported,
+            _ => CatalogError::Json(err),
+        })
+    }
+
+    /// Read a JSON Catalog, compressed or not, as a plain JSON value without decoding its tables.
+    /// Fields such as m_LocatorId, m_ProviderIds or m_resourceTypes can then be inspected cheaply, even on huge Catalogs.
+    pub fn as_json_value<S: AsRef<[u8]>>(slice: S) -> Result<serde_json::Value, CatalogError> {
+        serde_json::from_slice(&decompress(slice.as_ref())?).map_err(CatalogError::Json)
+    }
+
+    pub fn from_reader<R: Read>(mut reader: R) -> Result<Self, CatalogError> {
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        Self::from_slice(data)
+    }
+
+    /// Serialize the Catalog, either compact like Unity writes it or pretty-printed for editing by hand.
+    /// Both forms parse back to the same Catalog.
+    pub fn to_json(&self, pretty: bool) -> Result<String, CatalogError> {
+        let json = if pretty { serde_json::to_string_pretty(self) } else { serde_json::to_string(self) };
+        json.map_err(CatalogError::Json)
+    }
+
+    /// Serialize the Catalog like to_json, but straight to a writer instead of building the whole JSON in memory first.
+    /// Each table is still encoded to base64 on its own before being written, as serde needs the complete string.
+    pub fn write_to<W: Write>(&self, writer: W, pretty: bool) -> Result<(), CatalogError> {
+        let mut writer = std::io::BufWriter::new(writer);
+
+        if pretty {
+            serde_json::to_writer_pretty(&mut writer, self)?;
+        } else {
+            serde_json::to_writer(&mut writer, self)?;
+        }
+
+        writer.flush()?;
+
+        Ok(())
+    }
+
+    /// Open a Catalog split across several JSON files and merge them into one for querying.
+    /// The result can't be split back, so it should not be written over any of the shards.
+    #[cfg(feature = "edit")]
+    pub fn open_with_shards<P: AsRef<Path>>(main: P, extra: &[std::path::PathBuf]) -> Result<Self, CatalogError> {
+        let mut catalog = Self::open(main)?;
+
+        for shard in extra {
+            catalog.merge(Self::open(shard)?)?;
+        }
+
+        Ok(catalog)
+    }
+
+    /// Open a Catalog stored as the text asset of a bundle
+    pub fn from_bundle<P: AsRef<Path>>(path: P) -> Result<Self, CatalogError> {
+        let mut bundle = TextBundle::load(path)?;
+        Self::from_str(bundle.take_string()?)
+    }
+
+    /// Parse a Catalog from bytes that can either be a bundle or a JSON, compressed or not
+    pub fn load_auto(bytes: &[u8]) -> Result<Self, CatalogError> {
+        if is_bundle(bytes) {
+            Self::from_bundle_slice(bytes)
+        } else {
+            Self::from_slice(decompress(bytes)?)
+        }
+    }
+
+    /// Parse a Catalog from the raw bytes of a bundle already in memory
+    pub fn from_bundle_slice<S: AsRef<[u8]>>(slice: S) -> Result<Self, CatalogError> {
+        let mut bundle = TextBundle::from_slice(slice.as_ref())?;
+        Self::from_str(bundle.take_string()?)
+    }
+}
+
+impl Catalog {
+    pub fn locator_id(&self) -> &str {
+        &self.m_LocatorId
+    }
+
+    /// Expand an InternalId compressed as "<prefix index>#<remainder>" using m_InternalIdPrefixes.
+    /// InternalIds that do not use a prefix are returned as is.
+    pub fn expand_internal_id<'a>(&self, internal_id: &'a str) -> Cow<'a, str> {
+        if let Some((index, remainder)) = internal_id.rsplit_once('#') {
+            if let Some(prefix) = index.parse::<usize>().ok().and_then(|index| self.m_InternalIdPrefixes.get(index)) {
+                return Cow::Owned(format!("{}{}", prefix, remainder));
+            }
+        }
+
+        Cow::Borrowed(internal_id)
+    }
+
+    /// Compare a stored InternalId with a full one, as expand_internal_id would but without building the expanded string
+    fn internal_id_matches(&self, internal_id: &str, expanded: &str) -> bool {
+        if let Some((index, remainder)) = internal_id.rsplit_once('#') {
+            if let Some(prefix) = index.parse::<usize>().ok().and_then(|index| self.m_InternalIdPrefixes.get(index)) {
+                return expanded.strip_prefix(prefix.as_str()) == Some(remainder);
+            }
+        }
+
+        internal_id == expanded
     }
 
     pub fn get_internal_id_index<S: AsRef<str>>(&self, internal_id: S) -> Option<InternalId> {
         self.m_InternalIds
         .iter()
-        .position(|x| x == internal_id.as_ref())
+        .position(|x| self.internal_id_matches(x, internal_id.as_ref()))
         .map(InternalId::from)
     }
 
-    pub fn get_internal_id_from_index<I: Into<usize>>(&self, index: I) -> Option<&String> {
-        self.m_InternalIds.get(index.into())
+    pub fn get_internal_id_from_index<I: Into<usize>>(&self, index: I) -> Option<Cow<'_, str>> {
+        self.m_InternalIds.get(index.into()).map(|id| self.expand_internal_id(id))
     }
 
     pub fn get_internal_ids(&self) -> Vec<String> {
-        self.m_InternalIds.clone()
+        self.m_InternalIds.iter().map(|id| self.expand_internal_id(id).into_owned()).collect()
+    }
+
+    pub fn get_internal_id_prefixes(&self) -> &[String] {
+        &self.m_InternalIdPrefixes
     }
 
     pub fn get_key(&self, id: KeyId) -> Option<&KeyDataValue> {
         self.m_KeyDataString.entries.get(isize::from(id) as usize)
     }
 
+    pub fn find_key_by_string<S: AsRef<str>>(&self, string: S) -> Option<KeyId> {
+        self.m_KeyDataString.entries
+        .iter()
+        .position(|key| matches!(key, KeyDataValue::String { string: key, .. } if key == string.as_ref()))
+        .map(|index| KeyId(index as i32))
+    }
+
+    /// Iterate over the key table along with the id of each key
+    pub fn keys(&self) -> impl Iterator<Item = (KeyId, &KeyDataValue)> + '_ {
+        self.m_KeyDataString.entries.iter().enumerate().map(|(index, key)| (KeyId(index as i32), key))
+    }
+
+    pub fn hash_keys(&self) -> impl Iterator<Item = (KeyId, i32)> + '_ {
+        self.keys().filter_map(|(id, key)| {
+            match key {
+                KeyDataValue::String { .. } => None,
+                KeyDataValue::Hash(hash) => Some((id, *hash)),
+            }
+        })
+    }
+
+    /// Find the dependency key holding a hash, which also identifies its bucket
+    pub fn bucket_by_hash(&self, hash: i32) -> Option<KeyId> {
+        self.hash_keys().find(|(_, existing)| *existing == hash).map(|(id, _)| id)
+    }
+
     pub fn get_bucket(&self, id: KeyId) -> Option<&BucketEntry> {
         self.m_BucketDataString.entries.get(isize::from(id) as usize)
     }
 
-    pub fn get_bucket_mut(&mut self, id: KeyId) -> Option<&mut BucketEntry> {
-        self.m_BucketDataString.entries.get_mut(isize::from(id) as usize)
+    /// Iterate over the bucket table along with the id of the key each bucket belongs to
+    pub fn buckets(&self) -> impl Iterator<Item = (KeyId, &BucketEntry)> + '_ {
+        self.m_BucketDataString.entries.iter().enumerate().map(|(index, bucket)| (KeyId(index as i32), bucket))
+    }
+
+    /// Find the dependency hashes shared by keys with different dependencies, in the order of their first key.
+    /// Keys sharing a hash along with the same dependencies resolve the same either way and are not reported.
+    pub fn hash_collisions(&self) -> Vec<HashCollision> {
+        let mut by_hash: Vec<HashCollision> = Vec::new();
+        let mut positions: HashMap<i32, usize> = HashMap::new();
+
+        for (key, hash) in self.hash_keys() {
+            match positions.get(&hash) {
+                Some(position) => by_hash[*position].keys.push(key),
+                None => {
+                    positions.insert(hash, by_hash.len());
+                    by_hash.push(HashCollision { hash, keys: vec![key] });
+                },
+            }
+        }
+
+        by_hash
+        .into_iter()
+        .filter(|candidate| {
+            let first = self.get_bucket(candidate.keys[0]).map(|bucket| &bucket.indices);
+            candidate.keys[1..].iter().any(|key| self.get_bucket(*key).map(|bucket| &bucket.indices) != first)
+        })
+        .collect()
+    }
+
+    /// Compare the key offset of every bucket with where its key starts according to the size of the keys before it.
+    /// Unity relies on these offsets, so any mismatch breaks the Catalog in game even though it can still be read here.
+    pub fn validate_offsets(&self) -> Vec<KeyOffsetMismatch> {
+        let mut expected = 4;
+
+        self.buckets()
+        .zip(self.m_KeyDataString.entries.iter())
+        .filter_map(|((key, bucket), key_value)| {
+            let mismatch = (bucket.key_data_offset != expected).then_some(KeyOffsetMismatch { key, stored: bucket.key_data_offset, expected });
+            expected += key_value.get_size();
+            mismatch
+        })
+        .collect()
     }
 
     pub fn get_entry(&self, id: EntryId) -> Option<&EntryValue> {
         self.m_EntryDataString.entries.get(usize::from(id) as usize)
     }
 
+    pub fn get_entries(&self) -> &[EntryValue] {
+        &self.m_EntryDataString.entries
+    }
+
     pub fn get_entry_by_internal_id(&self, id: InternalId) -> Option<&EntryValue> {
         self.m_EntryDataString.entries.iter().find(|x| x.internal_id == id)
     }
 
+    #[deprecated(note = "use entry_id_of, which returns an EntryId")]
     pub fn get_entry_id_by_internal_id(&self, id: InternalId) -> Option<usize> {
         self.m_EntryDataString.entries.iter().position(|x| x.internal_id == id)
     }
 
+    /// Find the entry of an InternalId
+    pub fn entry_id_of(&self, id: InternalId) -> Option<EntryId> {
+        // The entry table can't hold more entries than an EntryId can index, as adding entries checks for it
+        self.m_EntryDataString.entries.iter().position(|x| x.internal_id == id).map(EntryId::from)
+    }
+
+    /// Find the entry of an InternalId string, prefixed or not
+    pub fn find_entry<S: AsRef<str>>(&self, internal_id: S) -> Option<EntryId> {
+        self.get_internal_id_index(internal_id).and_then(|id| self.entry_id_of(id))
+    }
+
     pub fn get_extra(&self, id: ExtraId) -> Option<&ExtraValue> {
         self.m_ExtraDataString.entries.get(isize::from(id) as usize)
     }
 
-    pub fn get_dependencies(&self, entry: &EntryValue) -> Option<&[EntryId]> {
-        Some(&self.get_bucket(entry.dependency_key_idx)?.indices)
+    /// Iterate over the extra data along with their id, as accepted by get_extra
+    pub fn extras(&self) -> impl Iterator<Item = (ExtraId, &ExtraValue)> + '_ {
+        self.m_ExtraDataString.entries.iter().enumerate().map(|(index, extra)| (ExtraId(index as i32), extra))
     }
 
-    pub fn add_internalid<S: AsRef<str>>(&mut self, internal_id: S) -> Result<InternalId, CatalogError> {
-        if self.get_internal_id_index(&internal_id).is_none() {
-            self.m_InternalIds.push(String::from(internal_id.as_ref()));
-            Ok((self.m_InternalIds.len() - 1).into())
-        } else {
-            Err(CatalogError::DuplicateInternalId)
-        }
+    /// Find the entry an extra data belongs to. Entries refer to it by its offset in the table rather than its id.
+    pub fn entry_for_extra(&self, id: ExtraId) -> Option<EntryId> {
+        let (offset, _) = self.extras_by_offset().nth(usize::try_from(id.0).ok()?)?;
+
+        self.m_EntryDataString.entries.iter().position(|entry| entry.data_index.0 == offset).map(EntryId::from)
     }
 
-    pub fn get_next_key_offset(&self) -> u32 {
-        self.m_BucketDataString.entries.last().unwrap().key_data_offset + self.m_KeyDataString.entries.last().unwrap().get_size()
+    /// Iterate over the extra data along with their offset in the table, which is what entries refer to them by
+    fn extras_by_offset(&self) -> impl Iterator<Item = (i32, &ExtraValue)> + '_ {
+        self.m_ExtraDataString.entries.iter().scan(0, |offset, extra| {
+            let entry = (*offset as i32, extra);
+            *offset += extra.get_size();
+            Some(entry)
+        })
     }
 
-    pub fn get_next_extra_offset(&self) -> u32 {
-        self.m_ExtraDataString.entries.iter().map(|extra| extra.get_size()).sum()
+    /// Resolve the extra data of an entry, if it has any
+    pub fn extra_of(&self, entry: &EntryValue) -> Option<&ExtraValue> {
+        self.extras_by_offset().find(|(offset, _)| *offset == entry.data_index.0).map(|(_, extra)| extra)
     }
 
-    pub fn get_unique_hash(&self) -> i32 {
-        let mut rng = rand::thread_rng();
-        let mut unique_value: i32 = rng.gen();
+    /// Encode an entry exactly as it is stored in the entry table
+    pub fn raw_entry(&self, id: EntryId) -> Option<Vec<u8>> {
+        encode_value(self.get_entry(id)?)
+    }
 
-        while self.m_KeyDataString.entries.iter().filter_map(|entry| {
-            match entry {
-                KeyDataValue::String { .. } => None,
-                KeyDataValue::Hash(hash) => Some(hash),
+    /// Encode a key exactly as it is stored in the key table
+    pub fn raw_key(&self, id: KeyId) -> Option<Vec<u8>> {
+        encode_value(self.get_key(id)?)
+    }
+
+    /// Encode the extra data of an entry exactly as it is stored in the extra table
+    pub fn raw_extra(&self, entry: &EntryValue) -> Option<Vec<u8>> {
+        encode_value(self.extra_of(entry)?)
+    }
+
+    /// Resolve the JSON stored in the extra data of an entry, if it has any
+    pub fn extra_json(&self, entry: &EntryValue) -> Option<&str> {
+        self.extra_of(entry)?.json_text()
+    }
+
+    /// Find the entries whose extra data contains the pattern in its assembly name, class name or JSON
+    pub fn search_extra<S: AsRef<str>>(&self, pattern: S) -> Vec<(EntryId, &ExtraValue)> {
+        let pattern = pattern.as_ref();
+
+        let extras: HashMap<i32, &ExtraValue> = self.extras_by_offset().collect();
+
+        self.m_EntryDataString.entries
+        .iter()
+        .enumerate()
+        .filter_map(|(index, entry)| Some((EntryId::from(index), *extras.get(&entry.data_index.0)?)))
+        .filter(|(_, extra)| [extra.assembly_name(), extra.class_name(), extra.json_text()].into_iter().flatten().any(|text| text.contains(pattern)))
+        .collect()
+    }
+
+    /// Resolve the type of the resource loaded by an entry
+    pub fn resource_type_of(&self, entry: &EntryValue) -> Option<&ObjectType> {
+        self.m_resourceTypes.get(usize::try_from(entry.resource_type).ok()?)
+    }
+
+    /// Find the entries whose resource type has this class name, such as UnityEngine.GameObject
+    pub fn find_entries_by_type(&self, class_name: &str) -> Vec<EntryId> {
+        self.get_entries()
+        .iter()
+        .enumerate()
+        .filter(|(_, entry)| self.resource_type_of(entry).is_some_and(|ty| ty.class_name() == class_name))
+        .map(|(index, _)| EntryId::from(index))
+        .collect()
+    }
+
+    /// Class name of every resource type along with how many entries use it, in the order of m_resourceTypes.
+    /// Entries pointing past the resource types are not counted.
+    pub fn resource_type_histogram(&self) -> Vec<(String, usize)> {
+        let mut counts = vec![0; self.m_resourceTypes.len()];
+
+        for entry in self.get_entries() {
+            if let Some(count) = usize::try_from(entry.resource_type).ok().and_then(|index| counts.get_mut(index)) {
+                *count += 1;
             }
-        }).any(|entry| entry == &unique_value) {
-            unique_value = rng.gen();
         }
 
-        unique_value
+        self.m_resourceTypes.iter().zip(counts).map(|(ty, count)| (ty.class_name().to_owned(), count)).collect()
     }
 
-    pub fn add_key(&mut self, key: KeyDataValue) -> KeyId {
-        let key_data_offset = self.get_next_key_offset();
+    /// List the provider ids, in the order entries refer to them by their provider_index
+    pub fn provider_ids(&self) -> &[String] {
+        &self.m_ProviderIds
+    }
 
-        // Add the dependency
-        self.m_KeyDataString.count += 1;
-        self.m_KeyDataString.entries.push(key);
+    /// Resolve the id of the provider used to load an entry
+    pub fn provider_of(&self, entry: &EntryValue) -> Option<&str> {
+        self.m_ProviderIds.get(entry.provider_index as usize).map(String::as_str)
+    }
 
-        // Get the current amount of EntryData entries, as our new entry will use the next index
-        self.m_BucketDataString.count += 1;
-        self.m_BucketDataString.entries.push(BucketEntry { key_data_offset, count: 1, indices: vec![EntryId(self.m_EntryDataString.entries.len() as u32)] });
+    /// Resolve the primary key of an entry as a string. Returns None if it is missing or a hash.
+    pub fn primary_key_string(&self, entry: &EntryValue) -> Option<&str> {
+        match self.get_key(entry.primary_key)? {
+            KeyDataValue::String { string, .. } => Some(string),
+            KeyDataValue::Hash(_) => None,
+        }
+    }
+
+    /// Resolve the primary key strings of many InternalIds with a single pass over the entry table,
+    /// instead of searching it again for every InternalId. Each result matches primary_key_string for the entry of that InternalId.
+    pub fn resolve_primary_keys(&self, ids: &[InternalId]) -> Vec<Option<&str>> {
+        let mut entries: HashMap<InternalId, &EntryValue> = HashMap::with_capacity(self.entry_count());
+
+        for entry in self.get_entries() {
+            // Like get_entry_by_internal_id, the first entry wins
+            entries.entry(entry.internal_id).or_insert(entry);
+        }
 
-        KeyId((self.m_KeyDataString.count - 1) as i32)
+        ids.iter().map(|id| entries.get(id).and_then(|entry| self.primary_key_string(entry))).collect()
     }
 
-    pub fn add_dependency_key(&mut self, key: KeyDataValue, dependencies: &[EntryId]) -> KeyId {
-        let key_data_offset = self.get_next_key_offset();
+    /// Resolve the key used to look up the dependencies of an entry
+    pub fn dependency_key(&self, entry: &EntryValue) -> Option<&KeyDataValue> {
+        self.get_key(entry.dependency_key_idx)
+    }
 
-        // Add the dependency
-        self.m_KeyDataString.count += 1;
-        self.m_KeyDataString.entries.push(key);
+    /// Number of entries. Counted from the table itself rather than from its stored count.
+    pub fn entry_count(&self) -> usize {
+        self.m_EntryDataString.entries.len()
+    }
 
-        self.m_BucketDataString.count += 1;
-        self.m_BucketDataString.entries.push(BucketEntry { key_data_offset, count: dependencies.len() as u32, indices: dependencies.to_vec() });
+    pub fn key_count(&self) -> usize {
+        self.m_KeyDataString.entries.len()
+    }
 
-        KeyId((self.m_KeyDataString.count - 1) as i32)
+    pub fn bucket_count(&self) -> usize {
+        self.m_BucketDataString.entries.len()
     }
 
-    pub fn add_extra_data(&mut self, extra: ExtraValue) -> ExtraId {
-        let offset = self.get_next_extra_offset();
-        // Add new extra entry
-        self.m_ExtraDataString.entries.push(extra);
+    /// Number of values in the extra data table, which is not the same as its size in bytes
+    pub fn extra_count(&self) -> usize {
+        self.m_ExtraDataString.entries.len()
+    }
 
-        // TODO: Make a method to calculate the size of the table. add_extradata
-        ExtraId(offset as i32)
+    pub fn internal_id_count(&self) -> usize {
+        self.m_InternalIds.len()
     }
 
-    pub fn add_bundle<S: AsRef<str>>(&mut self, internal_id: S, key: S, extra: ExtraValue) -> Result<(), CatalogError> {
-        // Try to add the internal ID, return a Duplicate error if it already exists
-        // TODO: This should be a method that combines both
-        let iid = self.add_internalid(&internal_id)?;
-        let primary_key = self.add_key(KeyDataValue::from_string(key.as_ref()));
+    /// Size in bytes of the extra data table
+    pub fn extra_data_size(&self) -> u32 {
+        self.m_ExtraDataString.serialized_size()
+    }
 
-        let new_entry = EntryValue { 
-            internal_id: iid,
-            provider_index: 0,
-            dependency_key_idx: KeyId(-1),
-            dependency_hash: 0,
-            data_index: self.add_extra_data(extra),
-            primary_key,
-            resource_type: 0,
-        };
+    pub fn get_dependencies(&self, entry: &EntryValue) -> Option<&[EntryId]> {
+        Some(&self.get_bucket(entry.dependency_key_idx)?.indices)
+    }
 
-        // Add new entry
-        self.m_EntryDataString.count += 1;
-        self.m_EntryDataString.entries.push(new_entry);
+    /// Hash of the dependency key of an entry. Returns None for bundles, which have none.
+    pub fn dependency_hash_of(&self, entry: &EntryValue) -> Option<i32> {
+        Some(entry.dependency_hash).filter(|hash| *hash != 0)
+    }
 
-        Ok(())
-    } 
+    /// Find the first entry linked to the dependency key with this hash
+    pub fn find_entry_by_dependency_hash(&self, hash: i32) -> Option<EntryId> {
+        if hash == 0 {
+            return None;
+        }
 
-    pub fn add_prefab<S: AsRef<str>>(&mut self, internal_id: S, key: S, dependencies: &[String]) -> Result<(), CatalogError> {
-        // TODO: This should be a method that combines both
-        // Try to add the internal ID, return a Duplicate error if it already exists
-        let iid = self.add_internalid(&internal_id)?;
-        let primary_key = self.add_key(KeyDataValue::from_string(key.as_ref()));
+        self.m_EntryDataString.entries.iter().position(|entry| entry.dependency_hash == hash).map(EntryId::from)
+    }
 
-        let hash = self.get_unique_hash();
+    /// List the bundles that are not in the dependencies of any entry, so nothing loads them
+    pub fn orphan_bundles(&self) -> Vec<EntryId> {
+        let referenced: HashSet<EntryId> = self
+            .hash_keys()
+            .filter_map(|(id, _)| self.get_bucket(id))
+            .flat_map(|bucket| bucket.indices.iter().copied())
+            .collect();
 
-        // Dependency stuff
-        // TODO: Turn this into a lookup method
-        let indices: Vec<EntryId> = dependencies.iter().flat_map(|dep| self.get_internal_id_index(dep)).flat_map(|id| self.get_entry_id_by_internal_id(id)).map(EntryId::from).collect();
-        // TODO: Generate the hash randomly. It cannot already exist in the Key table, so make sure it is unique.
-        let dependency_key_idx = self.add_dependency_key(KeyDataValue::Hash(hash), &indices);
+        self.m_EntryDataString.entries
+        .iter()
+        .enumerate()
+        .map(|(index, entry)| (EntryId::from(index), entry))
+        // Bundles are the only entries without a dependency hash
+        .filter(|(id, entry)| entry.dependency_hash == 0 && !referenced.contains(id))
+        .map(|(id, _)| id)
+        .collect()
+    }
 
-        let new_entry = EntryValue { 
-            internal_id: iid,
-            provider_index: 2,
-            dependency_key_idx: dependency_key_idx,
-            // TODO: Add a check to make sure the hash here matches with the dependency entry
-            dependency_hash: hash,
-            data_index: ExtraId(-1),
-            primary_key,
-            resource_type: 4,
-        };
+    /// Walk every entry reachable from the dependencies of an entry, depth-first.
+    /// Each entry is yielded once even if several paths lead to it, so dependency cycles can't loop forever.
+    /// Dependencies pointing to missing entries are yielded but not followed.
+    pub fn iter_dependencies_recursive<'a>(&'a self, entry: &EntryValue) -> impl Iterator<Item = EntryId> + 'a {
+        let mut visited = HashSet::new();
+        let mut pending: Vec<EntryId> = self.get_dependencies(entry).unwrap_or_default().iter().rev().copied().collect();
 
-        // Add new entry
-        self.m_EntryDataString.count += 1;
-        self.m_EntryDataString.entries.push(new_entry);
+        std::iter::from_fn(move || {
+            while let Some(entry_id) = pending.pop() {
+                if !visited.insert(entry_id) {
+                    continue;
+                }
 
-        Ok(())
-    } 
-}
\ No newline at end of file
+                if let Some(dependency) = self.get_entry(entry_id) {
+                    pending.extend(self.get_dependencies(dependency).unwrap_or_default().iter().rev());
+                }
+
+                return Some(entry_id);
+            }
+
+            None
+        })
+    }
+
+    /// List every entry reachable from the dependencies of an entry, depth-first and without duplicates
+    pub fn dependencies_recursive(&self, entry: &EntryValue) -> Vec<EntryId> {
+        self.iter_dependencies_recursive(entry).collect()
+    }
+
+    /// Sum the sizes recorded in the extra data of every bundle an entry depends on, directly or not.
+    /// Each bundle is counted once, and bundles without a recorded size count as empty.
+    #[cfg(feature = "json")]
+    pub fn recursive_size(&self, entry: &EntryValue) -> u64 {
+        self.iter_dependencies_recursive(entry)
+        .filter_map(|id| self.get_entry(id))
+        .filter(|dependency| dependency.dependency_hash == 0)
+        .filter_map(|bundle| self.extra_of(bundle)?.bundle_size())
+        .sum()
+    }
+
+    /// Resolve the InternalId, types, key and dependencies of an entry.
+    /// Returns None if the entry or anything it points to is missing.
+    pub fn entry_summary(&self, id: EntryId) -> Option<EntrySummary> {
+        let entry = self.get_entry(id)?;
+
+        let dependencies = self.get_dependencies(entry).unwrap_or_default().iter().map(|id| {
+            self.get_entry(*id).and_then(|dependency| self.get_internal_id_from_index(dependency.internal_id)).map(Cow::into_owned)
+        }).collect::<Option<Vec<String>>>()?;
+
+        // Make sure the key exists before treating a missing string as a hash key
+        self.get_key(entry.primary_key)?;
+
+        Some(EntrySummary {
+            internal_id: self.get_internal_id_from_index(entry.internal_id)?.into_owned(),
+            resource_type: self.resource_type_of(entry).map(|ty| ty.class_name().to_owned()),
+            provider: self.provider_of(entry).map(String::from),
+            primary_key: self.primary_key_string(entry).map(String::from),
+            dependencies,
+            // Bundles are the only entries without a dependency hash
+            is_bundle: entry.dependency_hash == 0,
+        })
+    }
+
+
+}
+
+#[cfg(feature = "edit")]
+impl Catalog {
+    /// Error for an InternalId no entry uses, naming it by its string, or by its index if it doesn't have one
+    fn missing_internal_id(&self, id: InternalId) -> CatalogError {
+        CatalogError::MissingInternalId(self.get_internal_id_from_index(id).map_or_else(|| format!("#{}", id.0), Cow::into_owned))
+    }
+
+    pub fn set_locator_id<S: Into<String>>(&mut self, id: S) {
+        self.m_LocatorId = id.into();
+    }
+
+    /// Register a new prefix for InternalIds to use and return its index. If the prefix already exists, the existing index is returned instead.
+    pub fn add_internal_id_prefix<S: AsRef<str>>(&mut self, prefix: S) -> usize {
+        match self.m_InternalIdPrefixes.iter().position(|x| x == prefix.as_ref()) {
+            Some(index) => index,
+            None => {
+                self.m_InternalIdPrefixes.push(String::from(prefix.as_ref()));
+                self.m_InternalIdPrefixes.len() - 1
+            }
+        }
+    }
+
+    pub fn get_bucket_mut(&mut self, id: KeyId) -> Option<&mut BucketEntry> {
+        self.m_BucketDataString.entries.get_mut(isize::from(id) as usize)
+    }
+
+    /// Edit an entry in place. Indices such as the provider or resource type are not checked, so they must point to existing values.
+    pub fn get_entry_mut(&mut self, id: EntryId) -> Option<&mut EntryValue> {
+        self.m_EntryDataString.entries.get_mut(usize::from(id))
+    }
+
+    /// Edit a key in place.
+    /// Buckets refer to keys by offset, so a key changing size must be followed by fixing the offset of every bucket after it.
+    /// Prefer set_primary_key_string, which takes care of it.
+    pub fn get_key_mut(&mut self, id: KeyId) -> Option<&mut KeyDataValue> {
+        self.m_KeyDataString.entries.get_mut(isize::from(id) as usize)
+    }
+
+    /// Edit extra data in place.
+    /// Entries refer to extra data by offset, so changing its size moves the extra data of every entry after it.
+    pub fn get_extra_mut(&mut self, id: ExtraId) -> Option<&mut ExtraValue> {
+        self.m_ExtraDataString.entries.get_mut(isize::from(id) as usize)
+    }
+
+    pub fn add_internalid<S: AsRef<str>>(&mut self, internal_id: S) -> Result<InternalId, CatalogError> {
+        if self.get_internal_id_index(&internal_id).is_none() {
+            let index = checked_index::<u32>(self.m_InternalIds.len())?;
+            self.m_InternalIds.push(String::from(internal_id.as_ref()));
+            Ok(InternalId(index))
+        } else {
+            Err(CatalogError::DuplicateInternalId(String::from(internal_id.as_ref())))
+        }
+    }
+
+    /// Merge InternalIds appearing several times into their first occurrence, which get_internal_id_index already resolves to.
+    /// Entries pointing at a later copy are pointed at the first one, and the InternalIds after a removed copy are shifted down.
+    /// Returns the amount of InternalIds removed.
+    pub fn dedup_internal_ids(&mut self) -> usize {
+        let mut first: HashMap<&str, u32> = HashMap::with_capacity(self.m_InternalIds.len());
+        let mut remap = Vec::with_capacity(self.m_InternalIds.len());
+        let mut kept = Vec::with_capacity(self.m_InternalIds.len());
+
+        for (index, internal_id) in self.m_InternalIds.iter().enumerate() {
+            match first.get(internal_id.as_str()) {
+                Some(new_index) => remap.push(*new_index),
+                None => {
+                    // The InternalIds already fit in a u32, so the fewer ones kept do too
+                    let new_index = kept.len() as u32;
+                    first.insert(internal_id, new_index);
+                    remap.push(new_index);
+                    kept.push(index);
+                },
+            }
+        }
+
+        let removed = self.m_InternalIds.len() - kept.len();
+
+        if removed == 0 {
+            return 0;
+        }
+
+        for entry in self.m_EntryDataString.entries.iter_mut() {
+            // Leave entries pointing past the table alone, validate reports them
+            if let Some(new_index) = remap.get(usize::from(entry.internal_id)) {
+                trace!("InternalId {} is now {}", entry.internal_id.0, new_index);
+                entry.internal_id = InternalId(*new_index);
+            }
+        }
+
+        let mut index = 0;
+        let kept: HashSet<usize> = kept.into_iter().collect();
+        self.m_InternalIds.retain(|_| {
+            let keep = kept.contains(&index);
+            index += 1;
+            keep
+        });
+
+        removed
+    }
+
+    /// Replace the extra data of an entry, such as the options of a bundle pointed at a new file.
+    /// Entries refer to extra data by offset, so those of the extra data after it are moved by the change in size.
+    pub fn set_extra(&mut self, entry: EntryId, extra: ExtraValue) -> Result<(), CatalogError> {
+        let data_index = self.get_entry(entry).ok_or(CatalogError::MissingEntry)?.data_index;
+
+        let index = self.extras_by_offset().position(|(offset, _)| offset == data_index.0).ok_or(CatalogError::MissingExtraData)?;
+        let delta = i64::from(extra.get_size()) - i64::from(self.m_ExtraDataString.entries[index].get_size());
+        let delta = i32::try_from(delta).map_err(|_| CatalogError::TableOverflow)?;
+
+        if delta != 0 {
+            for entry in self.m_EntryDataString.entries.iter_mut().filter(|entry| entry.data_index.0 > data_index.0) {
+                entry.data_index = ExtraId(entry.data_index.0.checked_add(delta).ok_or(CatalogError::TableOverflow)?);
+            }
+        }
+
+        self.m_ExtraDataString.entries[index] = extra;
+
+        Ok(())
+    }
+
+    /// Append a provider, along with the data Unity needs to instantiate it. Returns the index entries can refer to it by.
+    pub fn add_provider<S: Into<String>>(&mut self, id: S, object_type: ObjectType) -> Result<u32, CatalogError> {
+        let id = id.into();
+
+        if self.m_ProviderIds.contains(&id) {
+            return Err(CatalogError::DuplicateProvider(id));
+        }
+
+        let index = checked_index::<u32>(self.m_ProviderIds.len())?;
+        self.m_ResourceProviderData.push(ProviderData { m_Id: id.clone(), m_ObjectType: object_type, m_Data: String::new() });
+        self.m_ProviderIds.push(id);
+
+        Ok(index)
+    }
+
+    /// Change the id of a provider along with its data. Entries keep referring to it by the same index.
+    pub fn rename_provider<S: Into<String>>(&mut self, id: &str, new_id: S) -> Result<(), CatalogError> {
+        let new_id = new_id.into();
+
+        if self.m_ProviderIds.contains(&new_id) {
+            return Err(CatalogError::DuplicateProvider(new_id));
+        }
+
+        let index = self.m_ProviderIds.iter().position(|provider| provider == id).ok_or_else(|| CatalogError::MissingProvider(id.to_string()))?;
+
+        for data in self.m_ResourceProviderData.iter_mut().filter(|data| data.m_Id == id) {
+            data.m_Id = new_id.clone();
+        }
+
+        self.m_ProviderIds[index] = new_id;
+
+        Ok(())
+    }
+
+    /// New keys are appended, so they start where the key table currently ends
+    pub fn get_next_key_offset(&self) -> u32 {
+        self.m_KeyDataString.serialized_size()
+    }
+
+    pub fn get_next_extra_offset(&self) -> u32 {
+        self.extra_data_size()
+    }
+
+    /// Seed the generation of dependency hashes, so that the same edits always produce the same Catalog
+    pub fn set_hash_seed(&mut self, seed: u64) {
+        self.hash_rng = Some(StdRng::seed_from_u64(seed));
+    }
+
+    pub fn get_unique_hash(&mut self) -> i32 {
+        loop {
+            let unique_value: i32 = match self.hash_rng.as_mut() {
+                Some(rng) => rng.gen(),
+                None => rand::thread_rng().gen(),
+            };
+
+            if !self.hash_keys().any(|(_, hash)| hash == unique_value) {
+                return unique_value;
+            }
+        }
+    }
+
+    pub fn add_key(&mut self, key: KeyDataValue) -> Result<KeyId, CatalogError> {
+        // Get the current amount of EntryData entries, as our new entry will use the next index
+        let entry_id = EntryId(checked_index(self.m_EntryDataString.entries.len())?);
+        trace!("Bucket for the new key points to {:?}", entry_id);
+
+        self.push_key(key, vec![entry_id])
+    }
+
+    pub fn add_dependency_key(&mut self, key: KeyDataValue, dependencies: &[EntryId]) -> Result<KeyId, CatalogError> {
+        debug!("Adding dependency key '{}' with {} dependencies", key, dependencies.len());
+        trace!("Dependencies: {:?}", dependencies);
+
+        self.push_key(key, dependencies.to_vec())
+    }
+
+    /// Append a key that no entry uses yet, with an empty bucket. Hash keys must not already exist in the Catalog.
+    pub fn add_standalone_key(&mut self, key: KeyDataValue) -> Result<KeyId, CatalogError> {
+        if let KeyDataValue::Hash(hash) = key {
+            if self.bucket_by_hash(hash).is_some() {
+                return Err(CatalogError::DuplicateHash);
+            }
+        }
+
+        self.push_key(key, vec![])
+    }
+
+    /// Append a key along with the bucket holding its entry indices, making sure none of the counters overflow first
+    fn push_key(&mut self, key: KeyDataValue, indices: Vec<EntryId>) -> Result<KeyId, CatalogError> {
+        let key_id = KeyId(checked_index(self.m_KeyDataString.count as usize)?);
+        let key_count = self.m_KeyDataString.count.checked_add(1).ok_or(CatalogError::TableOverflow)?;
+        let bucket_count = self.m_BucketDataString.count.checked_add(1).ok_or(CatalogError::TableOverflow)?;
+        let indices_count = checked_index(indices.len())?;
+
+        let key_data_offset = self.get_next_key_offset();
+        debug!("Adding key '{}' at offset {:#x}", key, key_data_offset);
+
+        // Add the key
+        self.m_KeyDataString.count = key_count;
+        self.m_KeyDataString.entries.push(key);
+
+        self.m_BucketDataString.count = bucket_count;
+        self.m_BucketDataString.entries.push(BucketEntry { key_data_offset, count: indices_count, indices });
+
+        Ok(key_id)
+    }
+
+    /// Give a fresh unique hash to the keys of each hash collision whose dependencies differ from those of the first key,
+    /// updating the entries that use them. Keys with the same dependencies as the first one keep the hash. Returns how many keys were changed.
+    pub fn resolve_hash_collisions(&mut self) -> usize {
+        let mut changed = 0;
+
+        for collision in self.hash_collisions() {
+            let first = self.get_bucket(collision.keys[0]).map(|bucket| bucket.indices.clone());
+
+            for key_id in collision.keys.into_iter().skip(1) {
+                if self.get_bucket(key_id).map(|bucket| &bucket.indices) == first.as_ref() {
+                    continue;
+                }
+
+                let hash = self.get_unique_hash();
+                debug!("Replacing colliding hash {} of key {:?} with {}", collision.hash, key_id, hash);
+                self.m_KeyDataString.entries[isize::from(key_id) as usize] = KeyDataValue::Hash(hash);
+
+                for entry in self.m_EntryDataString.entries.iter_mut().filter(|entry| entry.dependency_key_idx == key_id) {
+                    entry.dependency_hash = hash;
+                }
+
+                changed += 1;
+            }
+        }
+
+        changed
+    }
+
+    /// Give every dependency key a fresh unique hash, updating the entries that use it in lockstep.
+    /// Entries sharing a dependency key keep sharing it, so they also end up with the same hash. Returns how many keys were changed.
+    pub fn regenerate_dependency_hashes(&mut self) -> usize {
+        let mut regenerated: HashMap<KeyId, i32> = HashMap::new();
+
+        for index in 0..self.m_EntryDataString.entries.len() {
+            let entry = &self.m_EntryDataString.entries[index];
+
+            // Bundles have no dependency hash
+            if entry.dependency_hash == 0 {
+                continue;
+            }
+
+            let key_id = entry.dependency_key_idx;
+
+            let hash = match regenerated.get(&key_id) {
+                Some(hash) => *hash,
+                None => {
+                    if !matches!(self.dependency_key(entry), Some(KeyDataValue::Hash(_))) {
+                        continue;
+                    }
+
+                    let hash = self.get_unique_hash();
+                    debug!("Replacing dependency hash of key {:?} with {}", key_id, hash);
+                    self.m_KeyDataString.entries[isize::from(key_id) as usize] = KeyDataValue::Hash(hash);
+                    regenerated.insert(key_id, hash);
+                    hash
+                }
+            };
+
+            self.m_EntryDataString.entries[index].dependency_hash = hash;
+        }
+
+        regenerated.len()
+    }
+
+    /// Append the content of another Catalog, remapping its indices and offsets to follow the existing tables.
+    /// InternalIds and keys are not deduplicated, so lookups find the ones of this Catalog first.
+    /// Providers missing from this Catalog are added along with their data. Dependency hashes the merge made collide are logged as warnings.
+    pub fn merge(&mut self, other: Catalog) -> Result<(), CatalogError> {
+        let internal_id_base = self.m_InternalIds.len();
+        let key_base = self.m_KeyDataString.entries.len();
+        let entry_base = self.m_EntryDataString.entries.len();
+        let extra_base = self.extra_data_size() as usize;
+        debug!("Merging a Catalog with {} entries", other.m_EntryDataString.entries.len());
+
+        // InternalIds are stored expanded, as the prefixes of both Catalogs don't line up
+        self.m_InternalIds.extend(other.get_internal_ids());
+
+        // Providers both Catalogs know keep the data of this one
+        for data in other.m_ResourceProviderData {
+            if !self.m_ResourceProviderData.iter().any(|x| x.m_Id == data.m_Id) {
+                self.m_ResourceProviderData.push(data);
+            }
+        }
+
+        let providers = other.m_ProviderIds.into_iter().map(|provider| {
+            let index = match self.m_ProviderIds.iter().position(|x| *x == provider) {
+                Some(index) => index,
+                None => {
+                    self.m_ProviderIds.push(provider);
+                    self.m_ProviderIds.len() - 1
+                }
+            };
+            checked_index(index)
+        }).collect::<Result<Vec<u32>, CatalogError>>()?;
+
+        let resource_types = other.m_resourceTypes.into_iter().map(|resource_type| {
+            let index = match self.m_resourceTypes.iter().position(|x| *x == resource_type) {
+                Some(index) => index,
+                None => {
+                    self.m_resourceTypes.push(resource_type);
+                    self.m_resourceTypes.len() - 1
+                }
+            };
+            checked_index(index)
+        }).collect::<Result<Vec<i32>, CatalogError>>()?;
+
+        for (key, bucket) in other.m_KeyDataString.entries.into_iter().zip(other.m_BucketDataString.entries) {
+            let indices = bucket.indices.iter().map(|id| checked_index(entry_base + usize::from(*id)).map(EntryId)).collect::<Result<Vec<EntryId>, CatalogError>>()?;
+            self.push_key(key, indices)?;
+        }
+
+        // -1 means the entry has no key or extra data, which must stay that way
+        let key = |id: KeyId| -> Result<KeyId, CatalogError> {
+            match usize::try_from(id.0) {
+                Ok(index) => Ok(KeyId(checked_index(key_base + index)?)),
+                Err(_) => Ok(id),
+            }
+        };
+
+        for entry in other.m_EntryDataString.entries {
+            let data_index = match usize::try_from(entry.data_index.0) {
+                Ok(offset) => ExtraId(checked_index(extra_base + offset)?),
+                Err(_) => entry.data_index,
+            };
+
+            self.m_EntryDataString.entries.push(EntryValue {
+                internal_id: InternalId(checked_index(internal_id_base + usize::from(entry.internal_id))?),
+                provider_index: providers.get(entry.provider_index as usize).copied().unwrap_or(entry.provider_index),
+                dependency_key_idx: key(entry.dependency_key_idx)?,
+                dependency_hash: entry.dependency_hash,
+                data_index,
+                primary_key: key(entry.primary_key)?,
+                resource_type: usize::try_from(entry.resource_type).ok().and_then(|index| resource_types.get(index).copied()).unwrap_or(entry.resource_type),
+            });
+        }
+
+        self.m_EntryDataString.count = checked_index(self.m_EntryDataString.entries.len())?;
+        self.m_ExtraDataString.entries.extend(other.m_ExtraDataString.entries);
+
+        for collision in self.hash_collisions() {
+            let keys: Vec<String> = collision.keys.iter().map(|key| isize::from(*key).to_string()).collect();
+            warn!("Dependency hash {} is shared by keys {} with different dependencies after merging", collision.hash, keys.join(", "));
+        }
+
+        Ok(())
+    }
+
+    /// Reset the count stored in every table and bucket to the amount of values it holds, in case an edit forgot to update one.
+    /// Returns how many counts were wrong, or TableOverflow if a table holds more values than its count can represent.
+    pub fn normalize_counts(&mut self) -> Result<usize, CatalogError> {
+        let mut fixed = 0;
+
+        let mut normalize = |count: &mut u32, len: usize, table: &str| -> Result<(), CatalogError> {
+            let len = checked_index(len)?;
+
+            if *count != len {
+                debug!("Fixing the count of {} from {} to {}", table, count, len);
+                *count = len;
+                fixed += 1;
+            }
+
+            Ok(())
+        };
+
+        normalize(&mut self.m_KeyDataString.count, self.m_KeyDataString.entries.len(), "the key table")?;
+        normalize(&mut self.m_BucketDataString.count, self.m_BucketDataString.entries.len(), "the bucket table")?;
+        normalize(&mut self.m_EntryDataString.count, self.m_EntryDataString.entries.len(), "the entry table")?;
+
+        for bucket in self.m_BucketDataString.entries.iter_mut() {
+            normalize(&mut bucket.count, bucket.indices.len(), "a bucket")?;
+        }
+
+        Ok(fixed)
+    }
+
+    /// Release the capacity the tables kept from bulk edits such as merges, without touching their content.
+    pub fn shrink_to_fit(&mut self) {
+        self.m_ProviderIds.shrink_to_fit();
+        self.m_InternalIds.shrink_to_fit();
+        self.m_InternalIdPrefixes.shrink_to_fit();
+        self.m_resourceTypes.shrink_to_fit();
+        self.m_KeyDataString.entries.shrink_to_fit();
+        self.m_BucketDataString.entries.shrink_to_fit();
+        self.m_EntryDataString.entries.shrink_to_fit();
+        self.m_ExtraDataString.entries.shrink_to_fit();
+
+        for bucket in self.m_BucketDataString.entries.iter_mut() {
+            bucket.indices.shrink_to_fit();
+        }
+    }
+
+    /// Rewrite the key offset of every bucket to where its key actually starts. Returns how many offsets were wrong.
+    pub fn fix_key_offsets(&mut self) -> usize {
+        let mismatches = self.validate_offsets().len();
+
+        if mismatches != 0 {
+            self.update_key_offsets(0);
+        }
+
+        mismatches
+    }
+
+    /// Replace the string of an entry's primary key. Entries sharing this key are affected as well.
+    /// Keys are referred to by offset, so the buckets of every key that follows are moved accordingly.
+    pub fn set_primary_key_string<S: Into<String>>(&mut self, entry: EntryId, new: S) -> Result<(), CatalogError> {
+        let key_id = self.get_entry(entry).ok_or(CatalogError::MissingEntry)?.primary_key;
+        let key_index = isize::from(key_id) as usize;
+
+        let key = self.m_KeyDataString.entries.get_mut(key_index).ok_or(CatalogError::MissingKey)?;
+
+        if let KeyDataValue::Hash(_) = key {
+            return Err(CatalogError::HashKey);
+        }
+
+        let new = KeyDataValue::from_string(new);
+        debug!("Replacing key '{}' with '{}'", key, new);
+        *key = new;
+
+        self.update_key_offsets(key_index + 1);
+
+        Ok(())
+    }
+
+    /// Point the buckets of every key starting from this index back at their key, after the size of a key before them changed
+    fn update_key_offsets(&mut self, first: usize) {
+        let mut offset = 4 + self.m_KeyDataString.entries[..first].iter().map(KeyDataValue::get_size).sum::<u32>();
+
+        for (bucket, key) in self.m_BucketDataString.entries.iter_mut().zip(self.m_KeyDataString.entries.iter()).skip(first) {
+            bucket.key_data_offset = offset;
+            offset += key.get_size();
+        }
+    }
+
+    /// Replace the start of every InternalId beginning with a prefix, to move assets between projects in bulk.
+    /// The primary keys of these entries are rewritten as well if they start with the same prefix.
+    /// Everything is checked before anything is changed. Returns how many InternalIds were rewritten.
+    pub fn rewrite_internal_ids(&mut self, from_prefix: &str, to_prefix: &str) -> Result<usize, CatalogError> {
+        let internal_ids = self.get_internal_ids();
+
+        let rewritten: Vec<(usize, String)> = internal_ids
+            .iter()
+            .enumerate()
+            .filter_map(|(index, id)| Some((index, format!("{}{}", to_prefix, id.strip_prefix(from_prefix)?))))
+            .collect();
+
+        // The new InternalIds must not collide with each other or with the ones left untouched
+        let mut final_ids: HashSet<&str> = internal_ids.iter().map(String::as_str).collect();
+
+        for (index, _) in rewritten.iter() {
+            final_ids.remove(internal_ids[*index].as_str());
+        }
+
+        for (_, new) in rewritten.iter() {
+            if !final_ids.insert(new) {
+                return Err(CatalogError::DuplicateInternalId(new.to_owned()));
+            }
+        }
+
+        let rewritten_ids: HashSet<InternalId> = rewritten.iter().map(|(index, _)| InternalId::from(*index)).collect();
+        let keys: HashSet<usize> = self.m_EntryDataString.entries
+            .iter()
+            .filter(|entry| rewritten_ids.contains(&entry.internal_id))
+            .map(|entry| isize::from(entry.primary_key) as usize)
+            .collect();
+
+        let mut first_key = None;
+
+        for index in keys {
+            let Some(KeyDataValue::String { string, .. }) = self.m_KeyDataString.entries.get(index) else {
+                continue;
+            };
+
+            if let Some(rest) = string.strip_prefix(from_prefix) {
+                let new = KeyDataValue::from_string(format!("{}{}", to_prefix, rest));
+                debug!("Replacing key '{}' with '{}'", string, new);
+                self.m_KeyDataString.entries[index] = new;
+                first_key = Some(first_key.map_or(index, |first: usize| first.min(index)));
+            }
+        }
+
+        if let Some(first_key) = first_key {
+            self.update_key_offsets(first_key + 1);
+        }
+
+        for (index, new) in rewritten.iter() {
+            debug!("Rewriting InternalId '{}' to '{}'", internal_ids[*index], new);
+            self.m_InternalIds[*index] = new.to_owned();
+        }
+
+        Ok(rewritten.len())
+    }
+
+    /// Move a bundle by replacing both its InternalId and the path used as its primary key.
+    /// Everything is checked before anything is changed, so a failure never leaves the bundle half-renamed.
+    pub fn rename_bundle_path<S: AsRef<str>>(&mut self, bundle: InternalId, new_internal_id: S, new_key: S) -> Result<(), CatalogError> {
+        let new_internal_id = new_internal_id.as_ref();
+
+        if self.get_internal_id_index(new_internal_id).is_some_and(|existing| existing != bundle) {
+            return Err(CatalogError::DuplicateInternalId(String::from(new_internal_id)));
+        }
+
+        let entry_id = self.entry_id_of(bundle).ok_or_else(|| self.missing_internal_id(bundle))?;
+        let entry = self.get_entry(entry_id).ok_or(CatalogError::MissingEntry)?;
+
+        if self.primary_key_string(entry).is_none() {
+            return Err(CatalogError::HashKey);
+        }
+
+        self.set_primary_key_string(entry_id, new_key.as_ref())?;
+
+        debug!("Renaming InternalId {:?} to '{}'", bundle, new_internal_id);
+        self.m_InternalIds[usize::from(bundle)] = String::from(new_internal_id);
+
+        Ok(())
+    }
+
+    pub fn add_extra_data(&mut self, extra: ExtraValue) -> Result<ExtraId, CatalogError> {
+        let offset = self.get_next_extra_offset();
+        let extra_id = ExtraId(checked_index(offset as usize)?);
+        debug!("Adding extra data of {} bytes at offset {:#x}", extra.get_size(), offset);
+        // Add new extra entry
+        self.m_ExtraDataString.entries.push(extra);
+
+        Ok(extra_id)
+    }
+
+    /// Add a bundle to the dependencies of a prefab, creating its dependency key if it had none
+    pub fn add_dependency_to_entry(&mut self, prefab: InternalId, bundle: InternalId) -> Result<(), CatalogError> {
+        let prefab_index = usize::from(self.entry_id_of(prefab).ok_or_else(|| self.missing_internal_id(prefab))?);
+        let bundle_id = self.entry_id_of(bundle).ok_or_else(|| self.missing_internal_id(bundle))?;
+
+        let dependency_key_idx = self.m_EntryDataString.entries[prefab_index].dependency_key_idx;
+
+        match self.get_bucket_mut(dependency_key_idx) {
+            Some(bucket) => {
+                if !bucket.indices.contains(&bundle_id) {
+                    bucket.count = bucket.count.checked_add(1).ok_or(CatalogError::TableOverflow)?;
+                    bucket.indices.push(bundle_id);
+                }
+            }
+            None => {
+                let hash = self.get_unique_hash();
+                let dependency_key_idx = self.add_dependency_key(KeyDataValue::Hash(hash), &[bundle_id])?;
+
+                let entry = &mut self.m_EntryDataString.entries[prefab_index];
+                entry.dependency_key_idx = dependency_key_idx;
+                entry.dependency_hash = hash;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Remove a bundle from the dependencies of a prefab
+    pub fn remove_dependency_from_entry(&mut self, prefab: InternalId, bundle: InternalId) -> Result<(), CatalogError> {
+        let prefab_index = usize::from(self.entry_id_of(prefab).ok_or_else(|| self.missing_internal_id(prefab))?);
+        let bundle_id = self.entry_id_of(bundle).ok_or_else(|| self.missing_internal_id(bundle))?;
+
+        // A prefab without a dependency bucket has no dependency to remove
+        let dependency_key_idx = self.m_EntryDataString.entries[prefab_index].dependency_key_idx;
+        let bucket = self.get_bucket_mut(dependency_key_idx).ok_or(CatalogError::NotADependency)?;
+        let position = bucket.indices.iter().position(|id| *id == bundle_id).ok_or(CatalogError::NotADependency)?;
+
+        bucket.indices.remove(position);
+        bucket.count = checked_index(bucket.indices.len())?;
+
+        Ok(())
+    }
+
+    /// Replace a bundle in the dependencies of a prefab with another, keeping its position in the bucket.
+    /// If the new bundle already is a dependency, the old one is only removed so no bundle is listed twice.
+    pub fn swap_dependency(&mut self, prefab: InternalId, old_bundle: InternalId, new_bundle: InternalId) -> Result<(), CatalogError> {
+        let prefab_index = usize::from(self.entry_id_of(prefab).ok_or_else(|| self.missing_internal_id(prefab))?);
+        let old_id = self.entry_id_of(old_bundle).ok_or_else(|| self.missing_internal_id(old_bundle))?;
+        let new_id = self.entry_id_of(new_bundle).ok_or_else(|| self.missing_internal_id(new_bundle))?;
+
+        let dependency_key_idx = self.m_EntryDataString.entries[prefab_index].dependency_key_idx;
+        let bucket = self.get_bucket_mut(dependency_key_idx).ok_or(CatalogError::NotADependency)?;
+        let position = bucket.indices.iter().position(|id| *id == old_id).ok_or(CatalogError::NotADependency)?;
+
+        if old_id != new_id && bucket.indices.contains(&new_id) {
+            bucket.indices.remove(position);
+            bucket.count = checked_index(bucket.indices.len())?;
+        } else {
+            bucket.indices[position] = new_id;
+        }
+
+        Ok(())
+    }
+
+    pub fn add_bundle<S: AsRef<str>>(&mut self, internal_id: S, key: S, extra: ExtraValue) -> Result<(), CatalogError> {
+        let entry_count = self.m_EntryDataString.count.checked_add(1).ok_or(CatalogError::TableOverflow)?;
+
+        // Try to add the internal ID, return a Duplicate error if it already exists
+        // TODO: This should be a method that combines both
+        let iid = self.add_internalid(&internal_id)?;
+        let primary_key = self.add_key(KeyDataValue::from_string(key.as_ref()))?;
+
+        let new_entry = EntryValue { 
+            internal_id: iid,
+            provider_index: 0,
+            dependency_key_idx: KeyId(-1),
+            dependency_hash: 0,
+            data_index: self.add_extra_data(extra)?,
+            primary_key,
+            resource_type: 0,
+        };
+
+        // Add new entry
+        self.m_EntryDataString.count = entry_count;
+        self.m_EntryDataString.entries.push(new_entry);
+
+        Ok(())
+    } 
+
+    pub fn add_prefab<S: AsRef<str>>(&mut self, internal_id: S, key: S, dependencies: &[String]) -> Result<(), CatalogError> {
+        let hash = self.get_unique_hash();
+        self.add_prefab_with_hash(internal_id, key, dependencies, hash)
+    }
+
+    /// Add a prefab with a specific dependency hash, such as one copied from another Catalog. The hash must not already be in use.
+    pub fn add_prefab_with_hash<S: AsRef<str>>(&mut self, internal_id: S, key: S, dependencies: &[String], hash: i32) -> Result<(), CatalogError> {
+        if self.bucket_by_hash(hash).is_some() {
+            return Err(CatalogError::DuplicateHash);
+        }
+
+        let entry_count = self.m_EntryDataString.count.checked_add(1).ok_or(CatalogError::TableOverflow)?;
+
+        // Every dependency has to exist before anything gets added
+        let indices: Vec<EntryId> = dependencies.iter().map(|dep| self.find_entry(dep).ok_or_else(|| CatalogError::MissingInternalId(dep.clone()))).collect::<Result<_, _>>()?;
+
+        // Try to add the internal ID, return a Duplicate error if it already exists
+        let iid = self.add_internalid(&internal_id)?;
+        let primary_key = self.add_key(KeyDataValue::from_string(key.as_ref()))?;
+        let dependency_key_idx = self.add_dependency_key(KeyDataValue::Hash(hash), &indices)?;
+
+        let new_entry = EntryValue { 
+            internal_id: iid,
+            provider_index: 2,
+            dependency_key_idx,
+            dependency_hash: hash,
+            data_index: ExtraId(-1),
+            primary_key,
+            resource_type: 4,
+        };
+
+        // Add new entry
+        self.m_EntryDataString.count = entry_count;
+        self.m_EntryDataString.entries.push(new_entry);
+
+        Ok(())
+    }
+
+    /// Duplicate an entry under a new InternalId and primary key, keeping its provider, type, extra data and dependencies.
+    /// The clone either shares the dependency key of the source, or gets a copy of it with a fresh hash so both can be edited separately.
+    pub fn clone_entry(&mut self, src: InternalId, new_id: &str, new_key: &str, share_dependencies: bool) -> Result<EntryId, CatalogError> {
+        let source = self.get_entry_by_internal_id(src).ok_or_else(|| self.missing_internal_id(src))?;
+        let (provider_index, data_index, resource_type) = (source.provider_index, source.data_index, source.resource_type);
+        let (mut dependency_key_idx, mut dependency_hash) = (source.dependency_key_idx, source.dependency_hash);
+
+        let entry_id = EntryId(checked_index(self.m_EntryDataString.entries.len())?);
+        let entry_count = self.m_EntryDataString.count.checked_add(1).ok_or(CatalogError::TableOverflow)?;
+
+        // Bundles have no dependency key to copy. The bucket is looked up before anything gets added.
+        let copied_indices = if !share_dependencies && dependency_hash != 0 {
+            Some(self.get_bucket(dependency_key_idx).ok_or(CatalogError::MissingKey)?.indices.clone())
+        } else {
+            None
+        };
+
+        let internal_id = self.add_internalid(new_id)?;
+        let primary_key = self.add_key(KeyDataValue::from_string(new_key))?;
+
+        if let Some(indices) = copied_indices {
+            dependency_hash = self.get_unique_hash();
+            dependency_key_idx = self.add_dependency_key(KeyDataValue::Hash(dependency_hash), &indices)?;
+        }
+
+        debug!("Cloning entry of {:?} as '{}' ({:?})", src, new_id, entry_id);
+
+        self.m_EntryDataString.count = entry_count;
+        self.m_EntryDataString.entries.push(EntryValue {
+            internal_id,
+            provider_index,
+            dependency_key_idx,
+            dependency_hash,
+            data_index,
+            primary_key,
+            resource_type,
+        });
+
+        Ok(entry_id)
+    }
+}
+
+#[cfg(all(test, feature = "edit", feature = "json"))]
+mod test {
+    use super::*;
+
+    const SHARED_BUNDLE: &str = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/common/gradients_emblemw_metal.bundle";
+    const BODY_BUNDLE: &str = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle";
+    const BODY_PREFAB: &str = "Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535.prefab";
+
+    /// Catalog with two bundles and a prefab depending on both
+    fn test_catalog() -> Catalog {
+        let mut catalog = Catalog::default();
+        catalog.add_bundle(SHARED_BUNDLE, "fe_assets_unit/model/common/gradients_emblemw_metal.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle(BODY_BUNDLE, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab(BODY_PREFAB, "Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535", &[BODY_BUNDLE.to_string(), SHARED_BUNDLE.to_string()]).unwrap();
+        catalog
+    }
+
+    fn bundle_extra(name: &str, crc: u32) -> ExtraValue {
+        ExtraValue::new(
+            "Unity.ResourceManager, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null".to_string(),
+            "UnityEngine.ResourceManagement.ResourceProviders.AssetBundleRequestOptions".to_string(),
+            format!("{{\"m_Hash\":\"\",\"m_Crc\":{},\"m_BundleName\":\"{}\",\"m_BundleSize\":1024}}", crc, name),
+        )
+    }
+
+    fn dependency_ids(catalog: &Catalog, internal_id: &str) -> Vec<String> {
+        let entry = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(internal_id).unwrap()).unwrap();
+        catalog.get_dependencies(entry).unwrap_or_default().iter().map(|id| {
+            catalog.get_internal_id_from_index(catalog.get_entry(*id).unwrap().internal_id).unwrap().into_owned()
+        }).collect()
+    }
+
+    #[test]
+    pub fn expand_prefixed_internal_ids() {
+        let mut catalog = Catalog::default();
+
+        let prefix = catalog.add_internal_id_prefix("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/");
+        assert_eq!(prefix, 0);
+        // Registering the same prefix twice should not duplicate it
+        assert_eq!(catalog.add_internal_id_prefix("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/"), 0);
+
+        catalog.m_InternalIds.push(format!("{}#byl0am/c535/prefabs/ubody_byl0am_c535.bundle", prefix));
+        catalog.m_InternalIds.push(String::from("Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535.prefab"));
+
+        let full_path = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle";
+
+        assert_eq!(catalog.get_internal_id_from_index(InternalId(0)).unwrap(), full_path);
+        assert_eq!(catalog.get_internal_id_index(full_path), Some(InternalId(0)));
+        // Neither the stored form nor the remainder alone are full paths
+        assert_eq!(catalog.get_internal_id_index("0#byl0am/c535/prefabs/ubody_byl0am_c535.bundle"), None);
+        assert_eq!(catalog.get_internal_id_index("byl0am/c535/prefabs/ubody_byl0am_c535.bundle"), None);
+        assert_eq!(catalog.get_internal_id_from_index(InternalId(1)).unwrap(), "Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535.prefab");
+        assert_eq!(catalog.get_internal_ids()[0], full_path);
+    }
+
+    #[test]
+    pub fn locator_id_round_trip() {
+        let mut catalog = Catalog::default();
+        catalog.set_locator_id("AddressablesMainContentCatalog");
+        catalog.set_locator_id(String::from("ModdedContentCatalog"));
+
+        let catalog = Catalog::from_str(serde_json::to_string(&catalog).unwrap()).unwrap();
+
+        assert_eq!(catalog.locator_id(), "ModdedContentCatalog");
+    }
+
+    #[test]
+    pub fn search_keys() {
+        let mut catalog = Catalog::default();
+        catalog.m_KeyDataString.entries.push(KeyDataValue::from_string("fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle"));
+        catalog.m_KeyDataString.entries.push(KeyDataValue::Hash(1234));
+        catalog.m_KeyDataString.entries.push(KeyDataValue::from_string("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535"));
+        catalog.m_KeyDataString.entries.push(KeyDataValue::Hash(-5678));
+
+        assert_eq!(catalog.find_key_by_string("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535"), Some(KeyId(2)));
+        assert_eq!(catalog.find_key_by_string("1234"), None);
+        assert_eq!(catalog.hash_keys().collect::<Vec<_>>(), vec![(KeyId(1), 1234), (KeyId(3), -5678)]);
+    }
+
+    #[test]
+    pub fn table_overflow() {
+        let mut catalog = Catalog::default();
+        catalog.m_KeyDataString.count = 1;
+        catalog.m_KeyDataString.entries.push(KeyDataValue::Hash(0));
+        catalog.m_BucketDataString.count = 1;
+        catalog.m_BucketDataString.entries.push(BucketEntry::default());
+
+        // Pretend the key table is full rather than allocating billions of keys
+        catalog.m_KeyDataString.count = i32::MAX as u32 + 1;
+        assert!(matches!(catalog.add_key(KeyDataValue::Hash(1)), Err(CatalogError::TableOverflow)));
+
+        catalog.m_KeyDataString.count = u32::MAX;
+        assert!(matches!(catalog.add_dependency_key(KeyDataValue::Hash(1), &[]), Err(CatalogError::TableOverflow)));
+
+        // Nothing should have been added by the failed calls
+        assert_eq!(catalog.m_KeyDataString.entries.len(), 1);
+        assert_eq!(catalog.m_BucketDataString.entries.len(), 1);
+
+        catalog.m_KeyDataString.count = 1;
+        assert_eq!(catalog.add_key(KeyDataValue::Hash(1)).unwrap(), KeyId(1));
+    }
+
+    #[test]
+    pub fn add_dependency() {
+        let mut catalog = test_catalog();
+        let extra_bundle = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/common/gradients_emblemw_skin.bundle";
+        catalog.add_bundle(extra_bundle, "fe_assets_unit/model/common/gradients_emblemw_skin.bundle", ExtraValue::default()).unwrap();
+
+        let prefab = catalog.get_internal_id_index(BODY_PREFAB).unwrap();
+        let bundle = catalog.get_internal_id_index(extra_bundle).unwrap();
+        catalog.add_dependency_to_entry(prefab, bundle).unwrap();
+        // Adding it a second time should not duplicate it
+        catalog.add_dependency_to_entry(prefab, bundle).unwrap();
+
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![BODY_BUNDLE, SHARED_BUNDLE, extra_bundle]);
+        let entry = catalog.get_entry_by_internal_id(prefab).unwrap();
+        assert_eq!(catalog.get_bucket(entry.dependency_key_idx).unwrap().count, 3);
+    }
+
+    #[test]
+    pub fn add_first_dependency() {
+        let mut catalog = test_catalog();
+
+        // Bundles do not have dependencies, so a new dependency key has to be created
+        let shared = catalog.get_internal_id_index(SHARED_BUNDLE).unwrap();
+        let body = catalog.get_internal_id_index(BODY_BUNDLE).unwrap();
+        catalog.add_dependency_to_entry(body, shared).unwrap();
+
+        assert_eq!(dependency_ids(&catalog, BODY_BUNDLE), vec![SHARED_BUNDLE]);
+        let entry = catalog.get_entry_by_internal_id(body).unwrap();
+        assert_eq!(catalog.get_key(entry.dependency_key_idx).map(|key| key.to_string()), Some(entry.dependency_hash.to_string()));
+    }
+
+    #[test]
+    pub fn remove_dependency() {
+        let mut catalog = test_catalog();
+
+        let prefab = catalog.get_internal_id_index(BODY_PREFAB).unwrap();
+        let bundle = catalog.get_internal_id_index(BODY_BUNDLE).unwrap();
+        catalog.remove_dependency_from_entry(prefab, bundle).unwrap();
+
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![SHARED_BUNDLE]);
+        let entry = catalog.get_entry_by_internal_id(prefab).unwrap();
+        assert_eq!(catalog.get_bucket(entry.dependency_key_idx).unwrap().count, 1);
+
+        // The bundle is no longer a dependency
+        assert!(matches!(catalog.remove_dependency_from_entry(prefab, bundle), Err(CatalogError::NotADependency)));
+
+        // Bundles have no dependency bucket at all
+        let shared = catalog.get_internal_id_index(SHARED_BUNDLE).unwrap();
+        assert!(matches!(catalog.remove_dependency_from_entry(bundle, shared), Err(CatalogError::NotADependency)));
+    }
+
+    #[test]
+    pub fn swap_dependency() {
+        let mut catalog = test_catalog();
+        let other_bundle = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c536/prefabs/ubody_byl0am_c536.bundle";
+        catalog.add_bundle(other_bundle, "fe_assets_unit/model/ubody/byl0am/c536/prefabs/ubody_byl0am_c536.bundle", ExtraValue::default()).unwrap();
+
+        let prefab = catalog.get_internal_id_index(BODY_PREFAB).unwrap();
+        let old_bundle = catalog.get_internal_id_index(BODY_BUNDLE).unwrap();
+        let new_bundle = catalog.get_internal_id_index(other_bundle).unwrap();
+        catalog.swap_dependency(prefab, old_bundle, new_bundle).unwrap();
+
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![other_bundle, SHARED_BUNDLE]);
+
+        // The old bundle is no longer a dependency
+        assert!(matches!(catalog.swap_dependency(prefab, old_bundle, new_bundle), Err(CatalogError::NotADependency)));
+    }
+
+    #[test]
+    pub fn swap_to_existing_dependency() {
+        let mut catalog = test_catalog();
+
+        let prefab = catalog.get_internal_id_index(BODY_PREFAB).unwrap();
+        let old_bundle = catalog.get_internal_id_index(BODY_BUNDLE).unwrap();
+        let new_bundle = catalog.get_internal_id_index(SHARED_BUNDLE).unwrap();
+        catalog.swap_dependency(prefab, old_bundle, new_bundle).unwrap();
+
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![SHARED_BUNDLE]);
+        let entry = catalog.get_entry_by_internal_id(prefab).unwrap();
+        assert_eq!(catalog.get_bucket(entry.dependency_key_idx).unwrap().count, 1);
+    }
+
+    #[test]
+    pub fn write_without_building_string() {
+        let catalog = test_catalog();
+
+        for pretty in [false, true] {
+            let mut written = Vec::new();
+            catalog.write_to(&mut written, pretty).unwrap();
+
+            assert_eq!(String::from_utf8(written).unwrap(), catalog.to_json(pretty).unwrap());
+        }
+    }
+
+    #[test]
+    pub fn merge_duplicate_internal_ids() {
+        let mut catalog = test_catalog();
+        // Another tool appended a copy of the body bundle, used by a second entry
+        catalog.m_InternalIds.push(BODY_BUNDLE.to_string());
+        catalog.m_InternalIds.push(String::from("Assets/Unit/after.prefab"));
+        catalog.m_EntryDataString.entries[1].internal_id = InternalId(3);
+        catalog.m_EntryDataString.entries[2].internal_id = InternalId(4);
+
+        assert_eq!(catalog.dedup_internal_ids(), 1);
+        assert_eq!(catalog.get_internal_ids(), vec![SHARED_BUNDLE, BODY_BUNDLE, BODY_PREFAB, "Assets/Unit/after.prefab"]);
+
+        let internal_ids: Vec<InternalId> = catalog.get_entries().iter().map(|entry| entry.internal_id).collect();
+        assert_eq!(internal_ids, vec![InternalId(0), InternalId(1), InternalId(3)]);
+
+        assert_eq!(catalog.dedup_internal_ids(), 0);
+    }
+
+    #[test]
+    pub fn add_and_rename_providers() {
+        let mut catalog = test_catalog();
+        catalog.m_ProviderIds = vec![String::from("AssetBundleProvider"), String::from("LegacyResourcesProvider"), String::from("BundledAssetProvider")];
+
+        let index = catalog.add_provider("Mod.CustomProvider", ObjectType::new("Mod", "Mod.CustomProvider")).unwrap();
+        assert_eq!(index, 3);
+        assert_eq!(catalog.provider_ids().len(), 4);
+        assert_eq!(catalog.m_ResourceProviderData[0].m_ObjectType.class_name(), "Mod.CustomProvider");
+        assert!(matches!(catalog.add_provider("AssetBundleProvider", ObjectType::default()), Err(CatalogError::DuplicateProvider(_))));
+
+        let prefab = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap().provider_index;
+        catalog.rename_provider("BundledAssetProvider", "Mod.BundledAssetProvider").unwrap();
+
+        // Entries are not reindexed
+        let prefab_entry = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+        assert_eq!(prefab_entry.provider_index, prefab);
+        assert_eq!(catalog.provider_of(prefab_entry), Some("Mod.BundledAssetProvider"));
+
+        assert!(matches!(catalog.rename_provider("BundledAssetProvider", "Other"), Err(CatalogError::MissingProvider(_))));
+        assert!(matches!(catalog.rename_provider("Mod.CustomProvider", "AssetBundleProvider"), Err(CatalogError::DuplicateProvider(_))));
+    }
+
+    #[test]
+    pub fn open_compressed() {
+        use std::io::Write;
+
+        let json = serde_json::to_string(&test_catalog()).unwrap();
+
+        let plain_path = std::env::temp_dir().join("catalog_open_plain.json");
+        std::fs::write(&plain_path, &json).unwrap();
+
+        let gzip_path = std::env::temp_dir().join("catalog_open_gzip.json.gz");
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+        std::fs::write(&gzip_path, encoder.finish().unwrap()).unwrap();
+
+        let brotli_path = std::env::temp_dir().join("catalog_open_brotli.json.br");
+        let mut compressed = Vec::new();
+        {
+            let mut writer = brotli::CompressorWriter::new(&mut compressed, 4096, 11, 22);
+            writer.write_all(json.as_bytes()).unwrap();
+        }
+        std::fs::write(&brotli_path, compressed).unwrap();
+
+        let plain = serde_json::to_string(&Catalog::open(&plain_path).unwrap()).unwrap();
+        let gzip = serde_json::to_string(&Catalog::open(&gzip_path).unwrap()).unwrap();
+        let brotli = serde_json::to_string(&Catalog::open(&brotli_path).unwrap()).unwrap();
+
+        assert_eq!(plain, json);
+        assert_eq!(gzip, plain);
+        assert_eq!(brotli, plain);
+
+        std::fs::remove_file(plain_path).unwrap();
+        std::fs::remove_file(gzip_path).unwrap();
+        std::fs::remove_file(brotli_path).unwrap();
+    }
+
+    #[test]
+    pub fn search_extra_data() {
+        let mut catalog = Catalog::default();
+        catalog.add_bundle(SHARED_BUNDLE, "fe_assets_unit/model/common/gradients_emblemw_metal.bundle", bundle_extra("gradients_emblemw_metal", 1111)).unwrap();
+        catalog.add_bundle(BODY_BUNDLE, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle", bundle_extra("ubody_byl0am_c535", 2222)).unwrap();
+        catalog.add_prefab(BODY_PREFAB, "Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535", &[BODY_BUNDLE.to_string()]).unwrap();
+
+        let found = catalog.search_extra("\"m_Crc\":2222");
+        assert_eq!(found.len(), 1);
+        assert_eq!(found[0].0, EntryId(1));
+        assert!(found[0].1.json_text().unwrap().contains("ubody_byl0am_c535"));
+
+        // Prefabs have no extra data, so only the bundles match the class name
+        assert_eq!(catalog.search_extra("AssetBundleRequestOptions").iter().map(|(id, _)| *id).collect::<Vec<_>>(), vec![EntryId(0), EntryId(1)]);
+        assert!(catalog.search_extra("missing").is_empty());
+    }
+
+    #[test]
+    pub fn shared_between_threads() {
+        fn assert_send_sync<T: Send + Sync>() {}
+        assert_send_sync::<Catalog>();
+
+        let catalog = std::sync::Arc::new(test_catalog());
+
+        let threads: Vec<_> = (0..4).map(|_| {
+            let catalog = std::sync::Arc::clone(&catalog);
+            std::thread::spawn(move || dependency_ids(&catalog, BODY_PREFAB))
+        }).collect();
+
+        for thread in threads {
+            assert_eq!(thread.join().unwrap(), vec![BODY_BUNDLE, SHARED_BUNDLE]);
+        }
+    }
+
+    #[test]
+    pub fn size_of_dependencies() {
+        let mut catalog = Catalog::default();
+        catalog.add_bundle(SHARED_BUNDLE, "fe_assets_unit/model/common/gradients_emblemw_metal.bundle", bundle_extra("gradients_emblemw_metal", 1111)).unwrap();
+        catalog.add_bundle(BODY_BUNDLE, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab(BODY_PREFAB, "Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535", &[BODY_BUNDLE.to_string(), SHARED_BUNDLE.to_string()]).unwrap();
+
+        // The body bundle has no recorded size
+        let prefab = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+        assert_eq!(catalog.recursive_size(prefab), 1024);
+
+        let bundle = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(SHARED_BUNDLE).unwrap()).unwrap();
+        assert_eq!(catalog.recursive_size(bundle), 0);
+    }
+
+    #[test]
+    pub fn extra_back_reference() {
+        let mut catalog = Catalog::default();
+        catalog.add_bundle(SHARED_BUNDLE, "fe_assets_unit/model/common/gradients_emblemw_metal.bundle", bundle_extra("gradients_emblemw_metal", 1111)).unwrap();
+        catalog.add_bundle(BODY_BUNDLE, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle", bundle_extra("ubody_byl0am_c535", 2222)).unwrap();
+        catalog.add_prefab(BODY_PREFAB, "Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535", &[BODY_BUNDLE.to_string()]).unwrap();
+
+        let extras: Vec<(ExtraId, &ExtraValue)> = catalog.extras().collect();
+        assert_eq!(extras.len(), 2);
+
+        for (id, extra) in extras {
+            assert_eq!(catalog.get_extra(id), Some(extra));
+
+            let owner = catalog.get_entry(catalog.entry_for_extra(id).unwrap()).unwrap();
+            assert_eq!(catalog.extra_of(owner), Some(extra));
+        }
+
+        assert_eq!(catalog.entry_for_extra(ExtraId(1)), Some(EntryId(1)));
+        assert_eq!(catalog.entry_for_extra(ExtraId(2)), None);
+        assert_eq!(catalog.entry_for_extra(ExtraId(-1)), None);
+    }
+
+    #[test]
+    pub fn resource_type_and_provider() {
+        let mut catalog = test_catalog();
+        catalog.m_ProviderIds = vec![
+            String::from("UnityEngine.ResourceManagement.ResourceProviders.AssetBundleProvider"),
+            String::from("UnityEngine.ResourceManagement.ResourceProviders.LegacyResourcesProvider"),
+            String::from("UnityEngine.ResourceManagement.ResourceProviders.BundledAssetProvider"),
+        ];
+        catalog.m_resourceTypes = vec![
+            ObjectType::new("Unity.ResourceManager", "UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource"),
+            ObjectType::new("UnityEngine.CoreModule", "UnityEngine.GameObject"),
+        ];
+
+        let bundle = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_BUNDLE).unwrap()).unwrap();
+        assert_eq!(catalog.provider_of(bundle), Some("UnityEngine.ResourceManagement.ResourceProviders.AssetBundleProvider"));
+        assert_eq!(catalog.resource_type_of(bundle).unwrap().class_name(), "UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource");
+
+        // The prefab points past the resource types registered above
+        let prefab = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+        assert_eq!(catalog.provider_of(prefab), Some("UnityEngine.ResourceManagement.ResourceProviders.BundledAssetProvider"));
+        assert!(catalog.resource_type_of(prefab).is_none());
+    }
+
+    #[test]
+    pub fn seeded_hashes() {
+        let dependency_hash = |seed| {
+            let mut catalog = Catalog::default();
+            catalog.set_hash_seed(seed);
+            catalog.add_bundle(BODY_BUNDLE, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle", ExtraValue::default()).unwrap();
+            catalog.add_prefab(BODY_PREFAB, "Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535", &[BODY_BUNDLE.to_string()]).unwrap();
+            catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap().dependency_hash
+        };
+
+        assert_eq!(dependency_hash(42), dependency_hash(42));
+        assert_ne!(dependency_hash(42), dependency_hash(43));
+    }
+
+    #[test]
+    pub fn summarize_entries() {
+        let mut catalog = test_catalog();
+        catalog.m_ProviderIds = vec![String::from("AssetBundleProvider"), String::from("LegacyResourcesProvider"), String::from("BundledAssetProvider")];
+
+        let prefab = catalog.entry_id_of(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+        assert_eq!(catalog.entry_summary(prefab), Some(EntrySummary {
+            internal_id: BODY_PREFAB.to_string(),
+            resource_type: None,
+            provider: Some(String::from("BundledAssetProvider")),
+            primary_key: Some(String::from("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535")),
+            dependencies: vec![BODY_BUNDLE.to_string(), SHARED_BUNDLE.to_string()],
+            is_bundle: false,
+        }));
+
+        let bundle = catalog.entry_summary(EntryId(0)).unwrap();
+        assert_eq!(bundle.internal_id, SHARED_BUNDLE);
+        assert!(bundle.is_bundle);
+        assert!(bundle.dependencies.is_empty());
+    }
+
+    #[test]
+    pub fn replace_primary_key_string() {
+        let mut catalog = test_catalog();
+
+        let bundle = catalog.entry_id_of(catalog.get_internal_id_index(BODY_BUNDLE).unwrap()).unwrap();
+        let new_path = "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535_2727518c6675e8bc51a36f771de88f3f.bundle";
+        catalog.set_primary_key_string(bundle, new_path).unwrap();
+
+        // Every bucket should still point at the start of its key
+        let mut offset = 4;
+        for (bucket, key) in catalog.m_BucketDataString.entries.iter().zip(catalog.m_KeyDataString.entries.iter()) {
+            assert_eq!(bucket.key_data_offset, offset);
+            offset += key.get_size();
+        }
+
+        let mut catalog = Catalog::from_str(serde_json::to_string(&catalog).unwrap()).unwrap();
+        assert!(catalog.find_key_by_string(new_path).is_some());
+        assert_eq!(catalog.entry_summary(EntryId::from(2usize)).unwrap().primary_key.as_deref(), Some("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535"));
+
+        // Dependency keys are hashes and can't be renamed
+        catalog.m_EntryDataString.entries[0].primary_key = catalog.get_entries()[2].dependency_key_idx;
+        assert!(matches!(catalog.set_primary_key_string(EntryId(0), "shared.bundle"), Err(CatalogError::HashKey)));
+
+        catalog.m_EntryDataString.entries[0].primary_key = KeyId(-1);
+        assert!(matches!(catalog.set_primary_key_string(EntryId(0), "shared.bundle"), Err(CatalogError::MissingKey)));
+        assert!(matches!(catalog.set_primary_key_string(EntryId(3), "shared.bundle"), Err(CatalogError::MissingEntry)));
+    }
+
+    #[test]
+    pub fn merge_shards() {
+        let mut catalog = test_catalog();
+        catalog.m_ProviderIds = vec![String::from("AssetBundleProvider"), String::from("LegacyResourcesProvider"), String::from("BundledAssetProvider")];
+
+        let mut shard = Catalog { m_ProviderIds: vec![String::from("AssetBundleProvider"), String::from("BundledAssetProvider")], ..Default::default() };
+        shard.add_provider("SceneProvider", ObjectType::new("Unity.ResourceManager", "SceneProvider")).unwrap();
+        shard.m_ProviderIds.swap(1, 2);
+        let prefix = shard.add_internal_id_prefix("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/");
+        shard.add_bundle(format!("{}#fe_assets_unit/model/common/gradients_emblemw_skin.bundle", prefix), String::from("fe_assets_unit/model/common/gradients_emblemw_skin.bundle"), bundle_extra("gradients_emblemw_skin", 5678)).unwrap();
+        shard.add_prefab(String::from("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab"), String::from("Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069"), &[String::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/common/gradients_emblemw_skin.bundle")]).unwrap();
+
+        catalog.merge(shard).unwrap();
+
+        assert_eq!(catalog.get_entries().len(), 5);
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![BODY_BUNDLE, SHARED_BUNDLE]);
+        assert_eq!(dependency_ids(&catalog, "Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab"), vec!["{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/common/gradients_emblemw_skin.bundle"]);
+
+        let skin = &catalog.get_entries()[3];
+        assert!(catalog.extra_of(skin).unwrap().json_text().unwrap().contains("\"m_Crc\":5678"));
+        assert_eq!(catalog.provider_of(&catalog.get_entries()[4]), Some("BundledAssetProvider"));
+
+        // The provider only the shard knows comes with its data
+        assert_eq!(catalog.provider_ids()[3], "SceneProvider");
+        assert_eq!(catalog.m_ResourceProviderData.len(), 1);
+        assert_eq!(catalog.m_ResourceProviderData[0].m_ObjectType.class_name(), "SceneProvider");
+
+        // Bucket offsets keep pointing at their key
+        let mut offset = 4;
+        for (bucket, key) in catalog.m_BucketDataString.entries.iter().zip(catalog.m_KeyDataString.entries.iter()) {
+            assert_eq!(bucket.key_data_offset, offset);
+            offset += key.get_size();
+        }
+    }
+
+    #[test]
+    pub fn pinned_prefab_hashes() {
+        let mut catalog = test_catalog();
+
+        catalog.add_prefab_with_hash("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab", "Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069", &[SHARED_BUNDLE.to_string()], 1234).unwrap();
+        catalog.add_prefab_with_hash("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c070/Prefabs/uBody_Cor0AF_c070.prefab", "Unit/Model/uBody/Cor0AF/c070/Prefabs/uBody_Cor0AF_c070", &[SHARED_BUNDLE.to_string()], 5678).unwrap();
+
+        let hashes: Vec<i32> = catalog.get_entries()[3..].iter().map(|entry| entry.dependency_hash).collect();
+        assert_eq!(hashes, vec![1234, 5678]);
+        assert_eq!(dependency_ids(&catalog, "Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c070/Prefabs/uBody_Cor0AF_c070.prefab"), vec![SHARED_BUNDLE]);
+
+        // Colliding hashes are refused before anything gets added
+        let entries = catalog.get_entries().len();
+        assert!(matches!(catalog.add_prefab_with_hash("Assets/Collision.prefab", "Collision", &[], 1234), Err(CatalogError::DuplicateHash)));
+        assert_eq!(catalog.get_entries().len(), entries);
+        assert!(catalog.get_internal_id_index("Assets/Collision.prefab").is_none());
+
+        // So are dependencies that don't resolve to an entry
+        let internal_ids = catalog.get_internal_ids();
+        let keys = catalog.key_count();
+        let missing = catalog.add_prefab_with_hash("Assets/Typo.prefab", "Typo", &[SHARED_BUNDLE.to_string(), String::from("Assets/Typo.bundle")], 9012);
+        assert!(matches!(missing, Err(CatalogError::MissingInternalId(internal_id)) if internal_id == "Assets/Typo.bundle"));
+        assert_eq!(catalog.get_internal_ids(), internal_ids);
+        assert_eq!(catalog.key_count(), keys);
+        assert_eq!(catalog.get_entries().len(), entries);
+    }
+
+    #[test]
+    pub fn describe_catalog() {
+        let mut catalog = test_catalog();
+        catalog.set_locator_id("AddressablesMainContentCatalog");
+
+        assert_eq!(catalog.to_string(), "Catalog 'AddressablesMainContentCatalog': 3 InternalIds, 4 keys, 3 entries, 14 bytes of extra data");
+
+        let debug = format!("{:?}", catalog);
+        assert!(debug.contains("locator_id: \"AddressablesMainContentCatalog\""));
+        assert!(debug.contains("keys: 4"));
+        assert!(!debug.contains(BODY_BUNDLE));
+    }
+
+    #[test]
+    pub fn regenerate_hashes() {
+        let mut catalog = test_catalog();
+        catalog.add_prefab_with_hash("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab", "Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069", &[SHARED_BUNDLE.to_string()], 1234).unwrap();
+        catalog.add_prefab_with_hash("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c070/Prefabs/uBody_Cor0AF_c070.prefab", "Unit/Model/uBody/Cor0AF/c070/Prefabs/uBody_Cor0AF_c070", &[SHARED_BUNDLE.to_string()], 5678).unwrap();
+        let dependencies = dependency_ids(&catalog, BODY_PREFAB);
+
+        assert_eq!(catalog.regenerate_dependency_hashes(), 3);
+
+        let hashes: Vec<i32> = catalog.hash_keys().map(|(_, hash)| hash).collect();
+        assert_eq!(hashes.len(), 3);
+        assert_eq!(hashes.iter().collect::<std::collections::HashSet<_>>().len(), 3);
+        assert!(!hashes.contains(&1234) && !hashes.contains(&5678));
+
+        for entry in catalog.get_entries().iter().filter(|entry| entry.dependency_hash != 0) {
+            assert!(matches!(catalog.get_key(entry.dependency_key_idx), Some(KeyDataValue::Hash(hash)) if *hash == entry.dependency_hash));
+        }
+
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), dependencies);
+    }
+
+    #[test]
+    pub fn detect_hash_collisions() {
+        let mut catalog = test_catalog();
+        let body_hash = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap().dependency_hash;
+        catalog.add_prefab_with_hash("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab", "Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069", &[SHARED_BUNDLE.to_string()], 1234).unwrap();
+        assert!(catalog.hash_collisions().is_empty());
+
+        // An imported key reusing the hash of the body prefab for other dependencies
+        let shared = catalog.entry_id_of(catalog.get_internal_id_index(SHARED_BUNDLE).unwrap()).unwrap();
+        let imported = catalog.add_dependency_key(KeyDataValue::Hash(body_hash), &[shared]).unwrap();
+        catalog.m_EntryDataString.entries[3].dependency_key_idx = imported;
+        catalog.m_EntryDataString.entries[3].dependency_hash = body_hash;
+
+        let collisions = catalog.hash_collisions();
+        assert_eq!(collisions.len(), 1);
+        assert_eq!(collisions[0].hash, body_hash);
+        assert_eq!(collisions[0].keys.len(), 2);
+        assert_eq!(collisions[0].keys[1], imported);
+
+        assert_eq!(catalog.resolve_hash_collisions(), 1);
+        assert!(catalog.hash_collisions().is_empty());
+        // The first key keeps its hash, and the entry of the other follows its new one
+        assert_eq!(catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap().dependency_hash, body_hash);
+        let entry = &catalog.get_entries()[3];
+        assert!(matches!(catalog.get_key(imported), Some(KeyDataValue::Hash(hash)) if *hash == entry.dependency_hash && *hash != body_hash));
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![BODY_BUNDLE, SHARED_BUNDLE]);
+    }
+
+    #[test]
+    pub fn keep_hash_of_identical_dependencies() {
+        let mut catalog = test_catalog();
+        let prefab = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+        let (body_hash, body_key) = (prefab.dependency_hash, prefab.dependency_key_idx);
+        let dependencies = catalog.get_dependencies(prefab).unwrap().to_vec();
+        let shared = catalog.entry_id_of(catalog.get_internal_id_index(SHARED_BUNDLE).unwrap()).unwrap();
+
+        // One copy of the body key with the same dependencies, and one with different ones
+        let same = catalog.add_dependency_key(KeyDataValue::Hash(body_hash), &dependencies).unwrap();
+        let different = catalog.add_dependency_key(KeyDataValue::Hash(body_hash), &[shared]).unwrap();
+        assert_eq!(catalog.hash_collisions()[0].keys, vec![body_key, same, different]);
+
+        assert_eq!(catalog.resolve_hash_collisions(), 1);
+        assert!(matches!(catalog.get_key(same), Some(KeyDataValue::Hash(hash)) if *hash == body_hash));
+        assert!(matches!(catalog.get_key(different), Some(KeyDataValue::Hash(hash)) if *hash != body_hash));
+    }
+
+    #[test]
+    pub fn replace_extra() {
+        let mut catalog = Catalog::default();
+        catalog.add_bundle(SHARED_BUNDLE, "fe_assets_unit/model/common/gradients_emblemw_metal.bundle", bundle_extra("gradients_emblemw_metal", 1111)).unwrap();
+        catalog.add_bundle(BODY_BUNDLE, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle", bundle_extra("ubody_byl0am_c535", 2222)).unwrap();
+        catalog.add_prefab(BODY_PREFAB, "Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535", &[BODY_BUNDLE.to_string()]).unwrap();
+
+        // The new name is longer, moving the extra data of the body bundle
+        let renamed = bundle_extra("gradients_emblemw_metal_v2", 3333);
+        catalog.set_extra(EntryId(0), renamed.clone()).unwrap();
+
+        let reopened = Catalog::from_str(catalog.to_json(false).unwrap()).unwrap();
+        assert_eq!(reopened.extra_of(reopened.get_entry(EntryId(0)).unwrap()), Some(&renamed));
+        assert_eq!(reopened.get_entry(EntryId(1)).unwrap().data_index, ExtraId(renamed.get_size() as i32));
+        assert_eq!(reopened.extra_of(reopened.get_entry(EntryId(1)).unwrap()).unwrap().bundle_crc(), Some(2222));
+
+        // Shrinking works the same way
+        catalog.set_extra(EntryId(0), ExtraValue::default()).unwrap();
+        assert_eq!(catalog.extra_of(catalog.get_entry(EntryId(1)).unwrap()).unwrap().bundle_crc(), Some(2222));
+
+        // The prefab has no extra data to replace
+        assert!(matches!(catalog.set_extra(EntryId(2), ExtraValue::default()), Err(CatalogError::MissingExtraData)));
+        assert!(matches!(catalog.set_extra(EntryId(3), ExtraValue::default()), Err(CatalogError::MissingEntry)));
+    }
+
+    #[test]
+    pub fn read_from_stream() {
+        use std::io::{Cursor, Write};
+
+        let json = serde_json::to_string(&test_catalog()).unwrap();
+
+        let mut encoder = flate2::write::GzEncoder::new(Vec::new(), flate2::Compression::default());
+        encoder.write_all(json.as_bytes()).unwrap();
+
+        let plain = read_catalog(Cursor::new(json.as_bytes()), false).unwrap();
+        let gzip = read_catalog(Cursor::new(encoder.finish().unwrap()), false).unwrap();
+
+        assert_eq!(serde_json::to_string(&plain).unwrap(), json);
+        assert_eq!(serde_json::to_string(&gzip).unwrap(), json);
+    }
+
+    #[test]
+    pub fn resolve_entry_keys() {
+        let catalog = test_catalog();
+
+        let prefab = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+        assert_eq!(catalog.primary_key_string(prefab), Some("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535"));
+        assert!(matches!(catalog.dependency_key(prefab), Some(KeyDataValue::Hash(hash)) if *hash == prefab.dependency_hash));
+
+        // A hash key has no string to resolve to
+        let hashed = EntryValue { primary_key: prefab.dependency_key_idx, ..*prefab };
+        assert_eq!(catalog.primary_key_string(&hashed), None);
+    }
+
+    #[test]
+    pub fn clone_prefab() {
+        let mut catalog = test_catalog();
+        let source = catalog.get_internal_id_index(BODY_PREFAB).unwrap();
+
+        let shared = catalog.clone_entry(source, "Assets/Unit/uBody_Shared.prefab", "Unit/uBody_Shared", true).unwrap();
+        let copied = catalog.clone_entry(source, "Assets/Unit/uBody_Copied.prefab", "Unit/uBody_Copied", false).unwrap();
+
+        let expected = dependency_ids(&catalog, BODY_PREFAB);
+        assert_eq!(dependency_ids(&catalog, "Assets/Unit/uBody_Shared.prefab"), expected);
+        assert_eq!(dependency_ids(&catalog, "Assets/Unit/uBody_Copied.prefab"), expected);
+
+        let source = catalog.get_entry_by_internal_id(source).unwrap();
+        let shared = catalog.get_entry(shared).unwrap();
+        let copied = catalog.get_entry(copied).unwrap();
+
+        assert_eq!(catalog.primary_key_string(shared), Some("Unit/uBody_Shared"));
+        assert_eq!(shared.dependency_key_idx, source.dependency_key_idx);
+        assert_ne!(copied.dependency_key_idx, source.dependency_key_idx);
+        assert!(matches!(catalog.dependency_key(copied), Some(KeyDataValue::Hash(hash)) if *hash == copied.dependency_hash));
+
+        assert!(matches!(catalog.clone_entry(InternalId(0), BODY_PREFAB, "Unit/Duplicate", true), Err(CatalogError::DuplicateInternalId(_))));
+    }
+
+    #[test]
+    pub fn clone_without_bucket() {
+        let mut catalog = test_catalog();
+        let source = catalog.get_internal_id_index(BODY_PREFAB).unwrap();
+        let index = usize::from(catalog.entry_id_of(source).unwrap());
+        catalog.m_EntryDataString.entries[index].dependency_key_idx = KeyId(-1);
+
+        let internal_ids = catalog.get_internal_ids();
+        let key_count = catalog.key_count();
+
+        assert!(matches!(catalog.clone_entry(source, "Assets/Unit/uBody_Copied.prefab", "Unit/uBody_Copied", false), Err(CatalogError::MissingKey)));
+        assert_eq!(catalog.get_internal_ids(), internal_ids);
+        assert_eq!(catalog.key_count(), key_count);
+    }
+
+    #[test]
+    pub fn detect_unsupported_layout() {
+        let json = serde_json::to_string(&test_catalog()).unwrap();
+        assert!(check_version(json.as_bytes()).is_ok());
+
+        // One entry of 8 fields instead of 7
+        let mut table = 1i32.to_le_bytes().to_vec();
+        table.extend([0u8; 32]);
+
+        let mut value: serde_json::Value = serde_json::from_str(&json).unwrap();
+        value["m_EntryDataString"] = serde_json::Value::String(base64::encode(table));
+
+        match Catalog::from_str(value.to_string()) {
+            Err(CatalogError::UnsupportedVersion(reason)) => assert_eq!(reason, "entries of 32 bytes instead of 28"),
+            other => panic!("expected an unsupported version, got {:?}", other),
+        }
+
+        value.as_object_mut().unwrap().remove("m_EntryDataString");
+        assert!(matches!(Catalog::from_str(value.to_string()), Err(CatalogError::UnsupportedVersion(_))));
+    }
+
+    #[test]
+    pub fn multi_level_dependencies() {
+        let mut catalog = test_catalog();
+        catalog.add_prefab("Assets/Unit/uBody_Variant.prefab", "Unit/uBody_Variant", &[String::from(BODY_PREFAB), String::from(SHARED_BUNDLE)]).unwrap();
+        catalog.add_prefab("Assets/Unit/Unit.prefab", "Unit/Unit", &[String::from("Assets/Unit/uBody_Variant.prefab")]).unwrap();
+
+        let unit = catalog.get_entry_by_internal_id(catalog.get_internal_id_index("Assets/Unit/Unit.prefab").unwrap()).unwrap();
+        let internal_ids: Vec<String> = catalog.dependencies_recursive(unit).into_iter().map(|id| {
+            catalog.get_internal_id_from_index(catalog.get_entry(id).unwrap().internal_id).unwrap().into_owned()
+        }).collect();
+
+        // The shared bundle is reached through both prefabs but only listed once
+        assert_eq!(internal_ids, vec!["Assets/Unit/uBody_Variant.prefab", BODY_PREFAB, BODY_BUNDLE, SHARED_BUNDLE]);
+    }
+
+    #[test]
+    pub fn rename_bundle() {
+        let mut catalog = test_catalog();
+        let bundle = catalog.get_internal_id_index(BODY_BUNDLE).unwrap();
+        let new_id = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535_moved.bundle";
+
+        assert!(matches!(catalog.rename_bundle_path(bundle, SHARED_BUNDLE, "shared"), Err(CatalogError::DuplicateInternalId(_))));
+        assert_eq!(catalog.get_internal_id_index(BODY_BUNDLE), Some(bundle));
+
+        catalog.rename_bundle_path(bundle, new_id, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535_moved.bundle").unwrap();
+
+        assert_eq!(catalog.get_internal_id_index(BODY_BUNDLE), None);
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![new_id, SHARED_BUNDLE]);
+
+        let entry = catalog.get_entry_by_internal_id(bundle).unwrap();
+        assert_eq!(catalog.primary_key_string(entry), Some("fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535_moved.bundle"));
+
+        // The keys that follow are untouched
+        let prefab = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+        assert_eq!(catalog.primary_key_string(prefab), Some("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535"));
+    }
+
+    #[test]
+    pub fn parse_extra_json() {
+        #[derive(Deserialize)]
+        struct AssetBundleRequestOptions {
+            #[serde(rename = "m_Hash")]
+            hash: String,
+            #[serde(rename = "m_Crc")]
+            crc: u32,
+            #[serde(rename = "m_BundleSize")]
+            bundle_size: u64,
+        }
+
+        let mut catalog = test_catalog();
+        let extra = ExtraValue::new(
+            "Unity.ResourceManager, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null",
+            "UnityEngine.ResourceManagement.ResourceProviders.AssetBundleRequestOptions",
+            "{\"m_Hash\":\"2727518c6675e8bc51a36f771de88f3f\",\"m_Crc\":3735928559,\"m_Timeout\":0,\"m_BundleSize\":20480}",
+        );
+        catalog.add_bundle("extra.bundle", "extra.bundle", extra).unwrap();
+
+        let entry = catalog.get_entry_by_internal_id(catalog.get_internal_id_index("extra.bundle").unwrap()).unwrap();
+        assert!(catalog.extra_json(entry).unwrap().contains("m_BundleSize"));
+
+        let options: AssetBundleRequestOptions = catalog.extra_of(entry).unwrap().parsed_json().unwrap();
+        assert_eq!(options.hash, "2727518c6675e8bc51a36f771de88f3f");
+        assert_eq!(options.crc, 3735928559);
+        assert_eq!(options.bundle_size, 20480);
+
+        assert!(ExtraValue::UInt32(0).parsed_json::<serde_json::Value>().is_err());
+    }
+
+    #[test]
+    pub fn compare_catalogs() {
+        let catalog = test_catalog();
+        let mut reopened = Catalog::from_str(serde_json::to_string(&catalog).unwrap()).unwrap();
+
+        // Seeding the hash generator does not change the content
+        reopened.set_hash_seed(0);
+        assert_eq!(reopened, catalog);
+
+        reopened.add_bundle("extra.bundle", "extra.bundle", ExtraValue::default()).unwrap();
+        assert_ne!(reopened, catalog);
+    }
+
+    #[test]
+    pub fn iterate_tables() {
+        let catalog = test_catalog();
+
+        let keys: Vec<String> = catalog.keys().map(|(_, key)| key.to_string()).collect();
+        assert_eq!(keys.len(), catalog.m_KeyDataString.entries.len());
+        assert!(keys.contains(&String::from("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535")));
+
+        // Every bucket belongs to the key with the same id and starts where it is stored
+        let mut offset = 4;
+        for ((key_id, key), (bucket_id, bucket)) in catalog.keys().zip(catalog.buckets()) {
+            assert_eq!(key_id, bucket_id);
+            assert_eq!(bucket.key_data_offset, offset);
+            offset += key.get_size();
+        }
+
+        assert_eq!(catalog.buckets().count(), catalog.keys().count());
+    }
+
+    #[test]
+    pub fn lookup_by_dependency_hash() {
+        let mut catalog = test_catalog();
+        catalog.set_hash_seed(42);
+        catalog.add_prefab("Assets/Unit/uBody_Variant.prefab", "Unit/uBody_Variant", &[String::from(BODY_BUNDLE)]).unwrap();
+
+        let entry_id = EntryId::from(catalog.get_entries().len() - 1);
+        let entry = catalog.get_entry(entry_id).unwrap();
+        let hash = catalog.dependency_hash_of(entry).unwrap();
+
+        assert_eq!(catalog.find_entry_by_dependency_hash(hash), Some(entry_id));
+        assert_eq!(catalog.bucket_by_hash(hash), Some(entry.dependency_key_idx));
+
+        let bundle = catalog.get_entry_by_internal_id(catalog.get_internal_id_index(BODY_BUNDLE).unwrap()).unwrap();
+        assert_eq!(catalog.dependency_hash_of(bundle), None);
+        assert_eq!(catalog.find_entry_by_dependency_hash(0), None);
+    }
+
+    #[test]
+    pub fn pretty_json() {
+        let catalog = test_catalog();
+
+        let compact = catalog.to_json(false).unwrap();
+        let pretty = catalog.to_json(true).unwrap();
+        assert_eq!(compact, serde_json::to_string(&catalog).unwrap());
+        assert!(pretty.lines().count() > 1);
+
+        assert_eq!(Catalog::from_str(&pretty).unwrap(), Catalog::from_str(&compact).unwrap());
+    }
+
+    #[test]
+    pub fn load_detected_format() {
+        let catalog = test_catalog();
+        let json = catalog.to_json(false).unwrap();
+
+        assert_eq!(Catalog::load_auto(json.as_bytes()).unwrap(), catalog);
+        assert_eq!(Catalog::try_from(json.as_bytes()).unwrap(), catalog);
+
+        // A truncated bundle goes to the bundle parser instead of the JSON one
+        let bundle: &[u8] = b"UnityFS\0\0\0\0\x085.x.x\0";
+        assert!(matches!(Catalog::load_auto(bundle), Err(CatalogError::Bundle(_))));
+    }
+
+    #[test]
+    pub fn find_orphan_bundles() {
+        let mut catalog = test_catalog();
+        assert!(catalog.orphan_bundles().is_empty());
+
+        catalog.add_bundle("orphan.bundle", "orphan.bundle", ExtraValue::default()).unwrap();
+        assert_eq!(catalog.orphan_bundles(), vec![EntryId::from(catalog.get_entries().len() - 1)]);
+    }
+
+    #[test]
+    pub fn standalone_keys() {
+        let mut catalog = test_catalog();
+        let key_count = catalog.keys().count();
+
+        let string = catalog.add_standalone_key(KeyDataValue::from_string("Unit/Model/uBody/Standalone")).unwrap();
+        let hash = catalog.add_standalone_key(KeyDataValue::Hash(31337)).unwrap();
+
+        assert_eq!(string, KeyId(key_count as i32));
+        assert_eq!(hash, KeyId(key_count as i32 + 1));
+        assert_eq!(catalog.find_key_by_string("Unit/Model/uBody/Standalone"), Some(string));
+        assert_eq!(catalog.bucket_by_hash(31337), Some(hash));
+        assert!(catalog.get_bucket(hash).unwrap().indices.is_empty());
+
+        assert!(matches!(catalog.add_standalone_key(KeyDataValue::Hash(31337)), Err(CatalogError::DuplicateHash)));
+    }
+
+    #[test]
+    pub fn rewrite_prefixes() {
+        let mut catalog = test_catalog();
+        catalog.add_bundle("mods/body.bundle", "mods/body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("Assets/Unit/uBody_Mod.prefab", "Unit/uBody_Mod", &[String::from("mods/body.bundle")]).unwrap();
+
+        let switch = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/";
+        let windows = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/StandaloneWindows64/";
+
+        assert_eq!(catalog.rewrite_internal_ids(switch, windows).unwrap(), 2);
+        assert_eq!(catalog.get_internal_id_index(BODY_BUNDLE), None);
+        assert_eq!(dependency_ids(&catalog, BODY_PREFAB), vec![BODY_BUNDLE.replace(switch, windows), SHARED_BUNDLE.replace(switch, windows)]);
+
+        // The key of this bundle mirrors its InternalId, so it follows it
+        assert_eq!(catalog.rewrite_internal_ids("mods/", "ported/").unwrap(), 1);
+        assert_eq!(dependency_ids(&catalog, "Assets/Unit/uBody_Mod.prefab"), vec!["ported/body.bundle"]);
+        let bundle = catalog.get_entry_by_internal_id(catalog.get_internal_id_index("ported/body.bundle").unwrap()).unwrap();
+        assert_eq!(catalog.primary_key_string(bundle), Some("ported/body.bundle"));
+
+        let mut offset = 4;
+        for ((_, key), (_, bucket)) in catalog.keys().zip(catalog.buckets()) {
+            assert_eq!(bucket.key_data_offset, offset);
+            offset += key.get_size();
+        }
+
+        // Rewriting onto an InternalId that already exists
+        assert!(matches!(catalog.rewrite_internal_ids("ported/body.bundle", "Assets/Unit/uBody_Mod.prefab"), Err(CatalogError::DuplicateInternalId(_))));
+    }
+
+    #[test]
+    pub fn table_counts() {
+        let mut catalog = test_catalog();
+        catalog.add_bundle("extra.bundle", "extra.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("Assets/Unit/uBody_Extra.prefab", "Unit/uBody_Extra", &[String::from("extra.bundle")]).unwrap();
+
+        assert_eq!(catalog.entry_count(), catalog.get_entries().len());
+        assert_eq!(catalog.internal_id_count(), catalog.get_internal_ids().len());
+        assert_eq!(catalog.key_count(), catalog.keys().count());
+        assert_eq!(catalog.bucket_count(), catalog.buckets().count());
+        assert_eq!(catalog.extra_count(), catalog.m_ExtraDataString.entries.len());
+
+        // The counts stored in the tables follow their content
+        assert_eq!(catalog.entry_count(), catalog.m_EntryDataString.count as usize);
+        assert_eq!(catalog.key_count(), catalog.m_KeyDataString.count as usize);
+        assert_eq!(catalog.bucket_count(), catalog.m_BucketDataString.count as usize);
+    }
+
+    #[test]
+    pub fn fix_drifting_counts() {
+        let mut catalog = test_catalog();
+        let expected = Catalog::from_str(catalog.to_json(false).unwrap()).unwrap();
+
+        catalog.m_EntryDataString.count += 1;
+        catalog.m_BucketDataString.entries[0].count = 0;
+        assert_ne!(catalog, expected);
+
+        assert_eq!(catalog.normalize_counts().unwrap(), 2);
+        assert_eq!(catalog.normalize_counts().unwrap(), 0);
+
+        let reopened = Catalog::from_str(catalog.to_json(false).unwrap()).unwrap();
+        assert_eq!(reopened, expected);
+    }
+
+    #[test]
+    pub fn shrink_tables() {
+        let mut catalog = test_catalog();
+        catalog.m_InternalIds.reserve(1000);
+        catalog.m_EntryDataString.entries.reserve(1000);
+        let expected = Catalog::from_str(catalog.to_json(false).unwrap()).unwrap();
+
+        catalog.shrink_to_fit();
+        assert!(catalog.m_InternalIds.capacity() < 1000);
+        assert!(catalog.m_EntryDataString.entries.capacity() < 1000);
+        assert_eq!(catalog, expected);
+    }
+
+    #[test]
+    pub fn null_provider_data() {
+        let mut value: serde_json::Value = serde_json::from_str(&test_catalog().to_json(false).unwrap()).unwrap();
+        value["m_InstanceProviderData"] = serde_json::Value::Null;
+        value["m_SceneProviderData"] = serde_json::Value::Null;
+
+        let catalog = Catalog::from_str(value.to_string()).unwrap();
+        assert!(catalog.m_InstanceProviderData.is_none());
+        assert!(catalog.m_SceneProviderData.is_none());
+
+        let written: serde_json::Value = serde_json::from_str(&catalog.to_json(false).unwrap()).unwrap();
+        assert!(written["m_InstanceProviderData"].is_null());
+        assert!(written["m_SceneProviderData"].is_null());
+    }
+
+    #[test]
+    pub fn batch_primary_keys() {
+        let catalog = test_catalog();
+        let ids = [
+            catalog.get_internal_id_index(BODY_PREFAB).unwrap(),
+            InternalId(42),
+            catalog.get_internal_id_index(SHARED_BUNDLE).unwrap(),
+        ];
+
+        let one_by_one: Vec<Option<&str>> = ids
+            .iter()
+            .map(|id| catalog.get_entry_by_internal_id(*id).and_then(|entry| catalog.primary_key_string(entry)))
+            .collect();
+
+        assert_eq!(catalog.resolve_primary_keys(&ids), one_by_one);
+        assert_eq!(one_by_one[0], Some("Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535"));
+        assert_eq!(one_by_one[1], None);
+    }
+
+    #[test]
+    pub fn inspect_without_tables() {
+        let mut catalog = test_catalog();
+        catalog.m_LocatorId = String::from("AddressablesMainContentCatalog");
+        catalog.m_ProviderIds = vec![String::from("UnityEngine.ResourceManagement.ResourceProviders.AssetBundleProvider")];
+
+        // A table that can't be decoded doesn't get in the way
+        let mut value: serde_json::Value = serde_json::from_str(&catalog.to_json(false).unwrap()).unwrap();
+        value["m_KeyDataString"] = serde_json::Value::String(String::from("not base64!"));
+        let json = value.to_string();
+        assert!(Catalog::from_str(&json).is_err());
+
+        let value = Catalog::as_json_value(&json).unwrap();
+        assert_eq!(value["m_LocatorId"], "AddressablesMainContentCatalog");
+        assert_eq!(value["m_ProviderIds"][0], "UnityEngine.ResourceManagement.ResourceProviders.AssetBundleProvider");
+        assert_eq!(value["m_InternalIds"].as_array().map(Vec::len), Some(3));
+    }
+
+    /// An example of the variant after this one, or None after the last.
+    /// There is no `_` arm, so a new variant does not compile until it is chained in here.
+    fn next_error(error: &CatalogError) -> Option<CatalogError> {
+        match error {
+            CatalogError::Io(_) => Some(CatalogError::Json(serde_json::from_str::<u32>("{").unwrap_err())),
+            CatalogError::Json(_) => Some(CatalogError::Base64Decode(base64::DecodeError::InvalidLength)),
+            CatalogError::Base64Decode(_) => Some(CatalogError::Bundle(anyhow::anyhow!("not a bundle"))),
+            CatalogError::Bundle(_) => Some(CatalogError::DuplicateInternalId(String::from(BODY_BUNDLE))),
+            CatalogError::DuplicateInternalId(_) => Some(CatalogError::MissingInternalId(String::from(BODY_PREFAB))),
+            CatalogError::MissingInternalId(_) => Some(CatalogError::TableOverflow),
+            CatalogError::TableOverflow => Some(CatalogError::HashKey),
+            CatalogError::HashKey => Some(CatalogError::DuplicateHash),
+            CatalogError::DuplicateHash => Some(CatalogError::MissingEntry),
+            CatalogError::MissingEntry => Some(CatalogError::MissingKey),
+            CatalogError::MissingKey => Some(CatalogError::MissingExtraData),
+            CatalogError::MissingExtraData => Some(CatalogError::NotADependency),
+            CatalogError::NotADependency => Some(CatalogError::DuplicateProvider(String::from("AssetBundleProvider"))),
+            CatalogError::DuplicateProvider(_) => Some(CatalogError::MissingProvider(String::from("SceneProvider"))),
+            CatalogError::MissingProvider(_) => Some(CatalogError::UnsupportedVersion(String::from("entries of 32 bytes"))),
+            CatalogError::UnsupportedVersion(_) => None,
+        }
+    }
+
+    #[test]
+    pub fn every_error_has_a_message() {
+        let first = CatalogError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "catalog.json"));
+        let errors: Vec<CatalogError> = std::iter::successors(Some(first), next_error).collect();
+        assert_eq!(errors.len(), 16);
+
+        for error in errors {
+            assert!(!error.to_string().is_empty(), "{:?} has no message", error);
+        }
+    }
+
+    #[test]
+    pub fn count_resource_types() {
+        let mut catalog = test_catalog();
+        catalog.add_bundle("extra.bundle", "extra.bundle", ExtraValue::default()).unwrap();
+        catalog.m_resourceTypes = vec![
+            ObjectType::new("Unity.ResourceManager", "UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource"),
+            ObjectType::new("UnityEngine.CoreModule", "UnityEngine.Texture2D"),
+        ];
+
+        // The prefab points past the resource types and is left out
+        assert_eq!(catalog.resource_type_histogram(), vec![
+            (String::from("UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource"), 3),
+            (String::from("UnityEngine.Texture2D"), 0),
+        ]);
+    }
+
+    #[test]
+    pub fn entries_of_a_type() {
+        let mut catalog = test_catalog();
+        catalog.m_resourceTypes = vec![
+            ObjectType::new("Unity.ResourceManager", "UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource"),
+            ObjectType::new("UnityEngine.CoreModule", "UnityEngine.Texture2D"),
+        ];
+
+        assert_eq!(catalog.find_entries_by_type("UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource"), vec![EntryId(0), EntryId(1)]);
+        assert!(catalog.find_entries_by_type("UnityEngine.Texture2D").is_empty());
+        assert!(catalog.find_entries_by_type("IAssetBundleResource").is_empty());
+    }
+
+    #[test]
+    pub fn wrong_key_offset() {
+        let mut catalog = test_catalog();
+        assert!(catalog.validate_offsets().is_empty());
+
+        let key = catalog.get_entries()[1].primary_key;
+        let expected = catalog.get_bucket(key).unwrap().key_data_offset;
+        catalog.get_bucket_mut(key).unwrap().key_data_offset += 3;
+
+        assert_eq!(catalog.validate_offsets(), vec![KeyOffsetMismatch { key, stored: expected + 3, expected }]);
+
+        assert_eq!(catalog.fix_key_offsets(), 1);
+        assert!(catalog.validate_offsets().is_empty());
+        assert_eq!(catalog.get_bucket(key).unwrap().key_data_offset, expected);
+    }
+
+    #[test]
+    pub fn edit_entry_in_place() {
+        let mut catalog = test_catalog();
+        let id = catalog.entry_id_of(catalog.get_internal_id_index(BODY_PREFAB).unwrap()).unwrap();
+
+        catalog.get_entry_mut(id).unwrap().provider_index = 1;
+
+        let reopened = Catalog::from_str(catalog.to_json(false).unwrap()).unwrap();
+        assert_eq!(reopened.get_entry(id).unwrap().provider_index, 1);
+        assert_eq!(reopened, catalog);
+    }
+}
+
+/// Round trips of randomly generated tables through the base64 codecs the Catalog JSON uses
+#[cfg(all(test, feature = "json"))]
+mod table_codecs {
+    use super::*;
+    use proptest::prelude::*;
+
+    /// Strings short enough for the fields Unity prefixes with a single byte length
+    fn short_string() -> impl Strategy<Value = String> {
+        ".{0,60}"
+    }
+
+    fn key() -> impl Strategy<Value = KeyDataValue> {
+        prop_oneof![
+            any::<String>().prop_map(KeyDataValue::from_string),
+            any::<i32>().prop_map(KeyDataValue::Hash),
+        ]
+    }
+
+    fn bucket() -> impl Strategy<Value = BucketEntry> {
+        (any::<u32>(), prop::collection::vec(any::<u32>().prop_map(EntryId), 0..8)).prop_map(|(key_data_offset, indices)| BucketEntry {
+            key_data_offset,
+            count: indices.len() as u32,
+            indices,
+        })
+    }
+
+    fn entry() -> impl Strategy<Value = EntryValue> {
+        (any::<u32>(), any::<u32>(), any::<i32>(), any::<i32>(), any::<i32>(), any::<i32>(), any::<i32>()).prop_map(
+            |(internal_id, provider_index, dependency_key_idx, dependency_hash, data_index, primary_key, resource_type)| EntryValue {
+                internal_id: InternalId(internal_id),
+                provider_index,
+                dependency_key_idx: KeyId(dependency_key_idx),
+                dependency_hash,
+                data_index: ExtraId(data_index),
+                primary_key: KeyId(primary_key),
+                resource_type,
+            },
+        )
+    }
+
+    fn extra() -> impl Strategy<Value = ExtraValue> {
+        prop_oneof![
+            any::<String>().prop_map(|string| ExtraValue::AsciiString { string }),
+            any::<String>().prop_map(|string| ExtraValue::UnicodeString { string }),
+            any::<u16>().prop_map(ExtraValue::UInt16),
+            any::<u32>().prop_map(ExtraValue::UInt32),
+            any::<i32>().prop_map(ExtraValue::Int32),
+            "[0-9a-f]{32}".prop_map(|hash| ExtraValue::Hash128 { hash }),
+            (short_string(), short_string()).prop_map(|(assembly_name, class_name)| ExtraValue::new_type(assembly_name, class_name)),
+            (short_string(), short_string(), any::<String>()).prop_map(|(assembly_name, class_name, json_text)| ExtraValue::new(assembly_name, class_name, json_text)),
+        ]
+    }
+
+    /// Encode a table the way it is stored in the JSON, then decode it back
+    fn round_trip<T: BinRead<Args<'static> = ()> + BinWrite<Args<'static> = ()>>(table: &T) -> T {
+        let encoded = serialize_catalog_table(table, serde_json::value::Serializer).unwrap();
+        deserialize_catalog_table(encoded).unwrap()
+    }
+
+    proptest! {
+        #[test]
+        fn key_table(entries in prop::collection::vec(key(), 0..16)) {
+            let table = KeyData { count: entries.len() as u32, entries };
+            prop_assert_eq!(round_trip(&table), table);
+        }
+
+        #[test]
+        fn bucket_table(entries in prop::collection::vec(bucket(), 0..16)) {
+            let table = BucketData { count: entries.len() as u32, entries };
+            prop_assert_eq!(round_trip(&table), table);
+        }
+
+        #[test]
+        fn entry_table(entries in prop::collection::vec(entry(), 0..16)) {
+            let table = EntryData { count: entries.len() as u32, entries };
+            prop_assert_eq!(round_trip(&table), table);
+        }
+
+        #[test]
+        fn extra_table(entries in prop::collection::vec(extra(), 0..16)) {
+            let table = ExtraData { entries };
+            prop_assert_eq!(round_trip(&table), table);
+        }
+    }
+}
@@ -1,2 +1,3 @@
 pub mod catalog;
-pub mod lookup;
\ No newline at end of file
+pub mod lookup;
+pub mod runtime_path;
\ No newline at end of file
@@ -1,7 +1,7 @@
 use std::{io::{ Seek, BufReader, Write }, fmt::Display};
-use binrw::{BinRead, BinWrite, BinReaderExt, BinResult, until_eof };
+use binrw::{binread, BinRead, BinWrite, BinReaderExt, BinResult, until_eof };
 
-#[derive(BinRead, BinWrite, Default)]
+#[derive(BinRead, BinWrite, Default, Debug, PartialEq)]
 #[brw(little)]
 pub struct KeyData {
     pub count: u32,
@@ -9,12 +9,21 @@ pub struct KeyData {
     pub entries: Vec<KeyDataValue>,
 }
 
-#[derive(BinRead, Debug)]
+impl KeyData {
+    /// Size of the table once serialized, including the count
+    pub fn serialized_size(&self) -> u32 {
+        4 + self.entries.iter().map(KeyDataValue::get_size).sum::<u32>()
+    }
+}
+
+#[binread]
+#[derive(Debug, PartialEq)]
 pub enum KeyDataValue {
     #[br(magic = 0u8)]
     String {
+        #[br(temp)]
         length: u32,
-        #[br(count = length, map = |x: Vec<u8>| String::from_utf8(x).unwrap())]
+        #[br(count = length, try_map = String::from_utf8)]
         string: String
     },
     #[br(magic = 4u8)]
@@ -32,13 +41,12 @@ impl Display for KeyDataValue {
 
 impl KeyDataValue {
     pub fn from_string<S: Into<String>>(internal_id: S) -> Self {
-        let string = internal_id.into();
-        KeyDataValue::String { length: string.len() as _, string }
+        KeyDataValue::String { string: internal_id.into() }
     }
 
     pub fn get_size(&self) -> u32 {
         match self {
-            KeyDataValue::String { length, .. } => *length + 5,
+            KeyDataValue::String { string } => string.len() as u32 + 5,
             KeyDataValue::Hash(_) => 5,
         }
     }
@@ -58,13 +66,13 @@ impl BinWrite for KeyDataValue {
                 (4u8, hash).write_options(writer, endian, args)
             },
             KeyDataValue::String { string, .. } => {
-                (0u8, string.len() as u32, string.as_bytes()).write_options(writer, endian, args)
+                (0u8, stored_len::<u32, _>(writer, string.len())?, string.as_bytes()).write_options(writer, endian, args)
             },
         }
     }
 }
 
-#[derive(BinRead, BinWrite, Default)]
+#[derive(BinRead, BinWrite, Default, Debug, PartialEq)]
 #[brw(little)]
 pub struct BucketData {
     pub count: u32,
@@ -72,7 +80,14 @@ pub struct BucketData {
     pub entries: Vec<BucketEntry>,
 }
 
-#[derive(BinRead, BinWrite, Default, Debug)]
+impl BucketData {
+    /// Size of the table once serialized, including the count
+    pub fn serialized_size(&self) -> u32 {
+        4 + self.entries.iter().map(BucketEntry::get_size).sum::<u32>()
+    }
+}
+
+#[derive(BinRead, BinWrite, Default, Debug, PartialEq)]
 pub struct BucketEntry {
     pub key_data_offset: u32,
     pub count: u32,
@@ -80,7 +95,13 @@ pub struct BucketEntry {
     pub indices: Vec<EntryId>,
 }
 
-#[derive(BinRead, BinWrite, Default)]
+impl BucketEntry {
+    pub fn get_size(&self) -> u32 {
+        4 + 4 + self.indices.len() as u32 * 4
+    }
+}
+
+#[derive(BinRead, BinWrite, Default, Debug, PartialEq)]
 #[brw(little)]
 pub struct EntryData {
     pub count: u32,
@@ -88,7 +109,14 @@ pub struct EntryData {
     pub entries: Vec<EntryValue>,
 }
 
-#[derive(BinRead, BinWrite, Debug)]
+impl EntryData {
+    /// Size of the table once serialized, including the count
+    pub fn serialized_size(&self) -> u32 {
+        4 + self.entries.iter().map(EntryValue::get_size).sum::<u32>()
+    }
+}
+
+#[derive(BinRead, BinWrite, Debug, PartialEq)]
 pub struct EntryValue {
     pub internal_id: InternalId,
     pub provider_index: u32,
@@ -99,7 +127,13 @@ pub struct EntryValue {
     pub resource_type: i32,
 }
 
-#[derive(BinRead, BinWrite, Default)]
+impl EntryValue {
+    pub fn get_size(&self) -> u32 {
+        7 * 4
+    }
+}
+
+#[derive(BinRead, BinWrite, Default, Debug, PartialEq)]
 #[brw(little)]
 pub struct ExtraData {
     #[br(parse_with = until_eof)]
@@ -107,32 +141,200 @@ pub struct ExtraData {
     pub entries: Vec<ExtraValue>,
 }
 
-#[derive(BinRead, Default, Clone, Debug)]
-#[brw(little)]
-pub struct ExtraValue {
-    // AsciiString,
-    // UnicodeString,
-    // UInt16,
-    // UInt32,
-    // Int32,
-    // Hash128,
-    // Type,
-    // > JsonObject
-    key_type: u8,
-    assembly_name_len: u8,
-    #[br(count = assembly_name_len, map = |x: Vec<u8>| String::from_utf8(x).unwrap())]
-    assembly_name: String,
-    class_name_len: u8,
-    #[br(count = class_name_len, map = |x: Vec<u8>| String::from_utf8(x).unwrap())]
-    class_name: String,
-    json_len: i32,
-    #[br(count = json_len, map = |x: Vec<u8>| String::from_utf8(x).unwrap())]
-    json_text: String,
+impl ExtraData {
+    /// Size of the table once serialized. Unlike the other tables, it does not start with a count.
+    pub fn serialized_size(&self) -> u32 {
+        self.entries.iter().map(ExtraValue::get_size).sum()
+    }
+}
+
+/// The lengths prefixing the strings are only read, and computed from the strings when writing
+#[binread]
+#[derive(Clone, Debug, PartialEq)]
+#[br(little)]
+pub enum ExtraValue {
+    #[br(magic = 0u8)]
+    AsciiString {
+        #[br(temp)]
+        length: i32,
+        #[br(count = length, try_map = String::from_utf8)]
+        string: String,
+    },
+    #[br(magic = 1u8)]
+    UnicodeString {
+        #[br(temp)]
+        length: i32,
+        #[br(count = length, try_map = utf16_to_string)]
+        string: String,
+    },
+    #[br(magic = 2u8)]
+    UInt16(u16),
+    #[br(magic = 3u8)]
+    UInt32(u32),
+    #[br(magic = 4u8)]
+    Int32(i32),
+    #[br(magic = 5u8)]
+    Hash128 {
+        #[br(temp)]
+        length: u8,
+        #[br(count = length, try_map = String::from_utf8)]
+        hash: String,
+    },
+    /// Reference to a type, such as an entry of m_resourceTypes
+    #[br(magic = 6u8)]
+    Type {
+        #[br(temp)]
+        assembly_name_len: u8,
+        #[br(count = assembly_name_len, try_map = String::from_utf8)]
+        assembly_name: String,
+        #[br(temp)]
+        class_name_len: u8,
+        #[br(count = class_name_len, try_map = String::from_utf8)]
+        class_name: String,
+    },
+    #[br(magic = 7u8)]
+    JsonObject {
+        #[br(temp)]
+        assembly_name_len: u8,
+        #[br(count = assembly_name_len, try_map = String::from_utf8)]
+        assembly_name: String,
+        #[br(temp)]
+        class_name_len: u8,
+        #[br(count = class_name_len, try_map = String::from_utf8)]
+        class_name: String,
+        /// Length of the json in bytes, which Unity stores as UTF-16
+        #[br(temp)]
+        json_len: i32,
+        #[br(count = json_len, try_map = utf16_to_string)]
+        json_text: String,
+    },
+}
+
+impl Default for ExtraValue {
+    fn default() -> Self {
+        ExtraValue::new("", "", "")
+    }
+}
+
+fn utf16_to_string(bytes: Vec<u8>) -> Result<String, String> {
+    if !bytes.len().is_multiple_of(2) {
+        return Err(format!("UTF-16 text of {} bytes, which is not a whole number of code units", bytes.len()));
+    }
+
+    let units: Vec<u16> = bytes.chunks_exact(2).map(|unit| u16::from_le_bytes([unit[0], unit[1]])).collect();
+    String::from_utf16(&units).map_err(|err| err.to_string())
+}
+
+/// Convert the length of a string to the integer type it is stored as, failing instead of writing a truncated length
+fn stored_len<T: TryFrom<usize>, W: Seek>(writer: &mut W, len: usize) -> BinResult<T> {
+    T::try_from(len).map_err(|_| binrw::Error::AssertFail {
+        pos: writer.stream_position().unwrap_or_default(),
+        message: format!("a string of {} bytes is too long for its length field", len),
+    })
+}
+
+fn string_to_utf16(string: &str) -> Vec<u8> {
+    string.encode_utf16().flat_map(u16::to_le_bytes).collect()
 }
 
 impl ExtraValue {
+    /// Create a JsonObject extra
+    pub fn new<S: Into<String>>(assembly_name: S, class_name: S, json_text: S) -> Self {
+        let assembly_name = assembly_name.into();
+        let class_name = class_name.into();
+        let json_text = json_text.into();
+
+        ExtraValue::JsonObject { assembly_name, class_name, json_text }
+    }
+
+    /// Create a Type extra
+    pub fn new_type<S: Into<String>>(assembly_name: S, class_name: S) -> Self {
+        let assembly_name = assembly_name.into();
+        let class_name = class_name.into();
+
+        ExtraValue::Type { assembly_name, class_name }
+    }
+
+    /// The ObjectType Unity serialized this extra as
+    pub fn key_type(&self) -> u8 {
+        match self {
+            ExtraValue::AsciiString { .. } => 0,
+            ExtraValue::UnicodeString { .. } => 1,
+            ExtraValue::UInt16(_) => 2,
+            ExtraValue::UInt32(_) => 3,
+            ExtraValue::Int32(_) => 4,
+            ExtraValue::Hash128 { .. } => 5,
+            ExtraValue::Type { .. } => 6,
+            ExtraValue::JsonObject { .. } => 7,
+        }
+    }
+
+    pub fn assembly_name(&self) -> Option<&str> {
+        match self {
+            ExtraValue::Type { assembly_name, .. } | ExtraValue::JsonObject { assembly_name, .. } => Some(assembly_name),
+            _ => None,
+        }
+    }
+
+    pub fn class_name(&self) -> Option<&str> {
+        match self {
+            ExtraValue::Type { class_name, .. } | ExtraValue::JsonObject { class_name, .. } => Some(class_name),
+            _ => None,
+        }
+    }
+
+    pub fn json_text(&self) -> Option<&str> {
+        match self {
+            ExtraValue::JsonObject { json_text, .. } => Some(json_text),
+            _ => None,
+        }
+    }
+
     pub fn get_size(&self) -> u32 {
-        (1 + 1 + self.assembly_name.len() + 1 + self.class_name.len() + 4 + self.json_text.len()) as u32
+        let payload = match self {
+            ExtraValue::AsciiString { string, .. } => 4 + string.len(),
+            ExtraValue::UnicodeString { string, .. } => 4 + string.encode_utf16().count() * 2,
+            ExtraValue::UInt16(_) => 2,
+            ExtraValue::UInt32(_) | ExtraValue::Int32(_) => 4,
+            ExtraValue::Hash128 { hash, .. } => 1 + hash.len(),
+            ExtraValue::Type { assembly_name, class_name, .. } => 1 + assembly_name.len() + 1 + class_name.len(),
+            ExtraValue::JsonObject { assembly_name, class_name, json_text, .. } => {
+                1 + assembly_name.len() + 1 + class_name.len() + 4 + json_text.encode_utf16().count() * 2
+            },
+        };
+
+        (1 + payload) as u32
+    }
+}
+
+/// Bundle options Unity stores as the JSON of AssetBundleRequestOptions extras
+#[cfg(feature = "json")]
+impl ExtraValue {
+    /// Parse the JSON of the extra data, such as the AssetBundleRequestOptions of a bundle, into a typed value
+    pub fn parsed_json<T: serde::de::DeserializeOwned>(&self) -> serde_json::Result<T> {
+        let json = self.json_text().ok_or_else(|| <serde_json::Error as serde::de::Error>::custom("the extra data is not a JSON object"))?;
+        serde_json::from_str(json)
+    }
+
+    fn json_field(&self, name: &str) -> Option<serde_json::Value> {
+        let mut json: serde_json::Value = self.parsed_json().ok()?;
+        Some(json.get_mut(name)?.take())
+    }
+
+    /// CRC recorded for the bundle, if any. Unity computes it over the uncompressed content, not the file itself.
+    pub fn bundle_crc(&self) -> Option<u32> {
+        self.json_field("m_Crc")?.as_u64().and_then(|crc| u32::try_from(crc).ok())
+    }
+
+    /// Size of the bundle file in bytes, if recorded
+    pub fn bundle_size(&self) -> Option<u64> {
+        self.json_field("m_BundleSize")?.as_u64()
+    }
+
+    /// Check a bundle file's size against the one recorded in its extra data.
+    /// Returns None if there is no size to compare with.
+    pub fn matches_bundle_size(&self, size: u64) -> Option<bool> {
+        self.bundle_size().map(|expected| expected == size)
     }
 }
 
@@ -145,8 +347,35 @@ impl BinWrite for ExtraValue {
         endian: binrw::Endian,
         args: Self::Args<'_>,
     ) -> BinResult<()> {
-        //panic!("{:?}", encoding_rs::UTF_16LE.encode(&self.json_text).0.into_owned());
-            (7u8, self.assembly_name.len() as u8, self.assembly_name.as_bytes(), self.class_name.len() as u8, self.class_name.as_bytes(), self.json_text.len() as i32, &self.json_text.as_bytes()).write_options(writer, endian, args)
+        let key_type = self.key_type();
+
+        match self {
+            ExtraValue::AsciiString { string } => {
+                (key_type, stored_len::<i32, _>(writer, string.len())?, string.as_bytes()).write_options(writer, endian, args)
+            },
+            ExtraValue::UnicodeString { string } => {
+                let string = string_to_utf16(string);
+                (key_type, stored_len::<i32, _>(writer, string.len())?, string).write_options(writer, endian, args)
+            },
+            ExtraValue::UInt16(value) => (key_type, value).write_options(writer, endian, args),
+            ExtraValue::UInt32(value) => (key_type, value).write_options(writer, endian, args),
+            ExtraValue::Int32(value) => (key_type, value).write_options(writer, endian, args),
+            ExtraValue::Hash128 { hash } => {
+                (key_type, stored_len::<u8, _>(writer, hash.len())?, hash.as_bytes()).write_options(writer, endian, args)
+            },
+            ExtraValue::Type { assembly_name, class_name } => {
+                let assembly_name_len = stored_len::<u8, _>(writer, assembly_name.len())?;
+                let class_name_len = stored_len::<u8, _>(writer, class_name.len())?;
+                (key_type, assembly_name_len, assembly_name.as_bytes(), class_name_len, class_name.as_bytes()).write_options(writer, endian, args)
+            },
+            ExtraValue::JsonObject { assembly_name, class_name, json_text } => {
+                let json_text = string_to_utf16(json_text);
+                let assembly_name_len = stored_len::<u8, _>(writer, assembly_name.len())?;
+                let class_name_len = stored_len::<u8, _>(writer, class_name.len())?;
+                let json_len = stored_len::<i32, _>(writer, json_text.len())?;
+                (key_type, assembly_name_len, assembly_name.as_bytes(), class_name_len, class_name.as_bytes(), json_len, json_text).write_options(writer, endian, args)
+            },
+        }
     }
 }
 
@@ -236,4 +465,144 @@ impl From<isize> for ExtraId {
     fn from(index: isize) -> Self {
         ExtraId(index as i32)
     }
-}
\ No newline at end of file
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    fn written_len<T: BinWrite<Args<'static> = ()>>(table: &T) -> u32 {
+        let mut buf = std::io::Cursor::new(Vec::new());
+        table.write_le_args(&mut buf, ()).unwrap();
+        buf.into_inner().len() as u32
+    }
+
+    #[test]
+    pub fn serialized_sizes() {
+        let keys = KeyData {
+            count: 2,
+            entries: vec![KeyDataValue::from_string("fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle"), KeyDataValue::Hash(1234)],
+        };
+        assert_eq!(keys.serialized_size(), written_len(&keys));
+
+        let buckets = BucketData {
+            count: 2,
+            entries: vec![
+                BucketEntry { key_data_offset: 4, count: 1, indices: vec![EntryId(0)] },
+                BucketEntry { key_data_offset: 83, count: 3, indices: vec![EntryId(0), EntryId(1), EntryId(2)] },
+            ],
+        };
+        assert_eq!(buckets.serialized_size(), written_len(&buckets));
+
+        let entries = EntryData {
+            count: 1,
+            entries: vec![EntryValue {
+                internal_id: InternalId(0),
+                provider_index: 2,
+                dependency_key_idx: KeyId(1),
+                dependency_hash: 1234,
+                data_index: ExtraId(-1),
+                primary_key: KeyId(0),
+                resource_type: 4,
+            }],
+        };
+        assert_eq!(entries.serialized_size(), written_len(&entries));
+
+        let extras = ExtraData {
+            entries: vec![
+                ExtraValue::new(
+                    "Unity.ResourceManager, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null",
+                    "UnityEngine.ResourceManagement.ResourceProviders.AssetBundleRequestOptions",
+                    "{\"m_Hash\":\"\",\"m_Crc\":0,\"m_BundleSize\":1024}",
+                ),
+                ExtraValue::AsciiString { string: String::from("ascii") },
+                ExtraValue::UnicodeString { string: String::from("unicode") },
+                ExtraValue::UInt16(16),
+                ExtraValue::Hash128 { hash: String::from("abcd") },
+                ExtraValue::default(),
+            ],
+        };
+        assert_eq!(extras.serialized_size(), written_len(&extras));
+    }
+
+    #[test]
+    pub fn extra_round_trip() {
+        let bytes: Vec<u8> = [
+            &[0u8, 5, 0, 0, 0][..], b"ascii",
+            &[1, 4, 0, 0, 0, b'h', 0, b'i', 0],
+            &[2, 0x10, 0x00],
+            &[3, 0x20, 0, 0, 0],
+            &[4, 0xff, 0xff, 0xff, 0xff],
+            &[5, 4], b"abcd",
+            &[7, 1, b'a', 1, b'c', 4, 0, 0, 0, b'{', 0, b'}', 0],
+        ].concat();
+
+        let extras: ExtraData = std::io::Cursor::new(&bytes).read_le().unwrap();
+        assert_eq!(extras.entries.len(), 7);
+        assert!(matches!(&extras.entries[0], ExtraValue::AsciiString { string, .. } if string == "ascii"));
+        assert!(matches!(&extras.entries[1], ExtraValue::UnicodeString { string, .. } if string == "hi"));
+        assert!(matches!(extras.entries[4], ExtraValue::Int32(-1)));
+        assert_eq!(extras.entries[6].json_text(), Some("{}"));
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        extras.write_le(&mut buf).unwrap();
+        assert_eq!(buf.into_inner(), bytes);
+        assert_eq!(extras.serialized_size() as usize, bytes.len());
+    }
+
+    #[test]
+    pub fn type_round_trip() {
+        let bytes: Vec<u8> = [&[6u8, 8][..], b"Assembly", &[10], b"Some.Class"].concat();
+
+        let extra: ExtraValue = std::io::Cursor::new(&bytes).read_le().unwrap();
+        assert_eq!(extra, ExtraValue::new_type("Assembly", "Some.Class"));
+        assert_eq!(extra.assembly_name(), Some("Assembly"));
+        assert_eq!(extra.class_name(), Some("Some.Class"));
+        assert_eq!(extra.json_text(), None);
+        assert_eq!(extra.get_size() as usize, bytes.len());
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        extra.write_le(&mut buf).unwrap();
+        assert_eq!(buf.into_inner(), bytes);
+    }
+
+    #[test]
+    pub fn malformed_strings() {
+        // Invalid UTF-8
+        let bytes: Vec<u8> = [&[0u8, 2, 0, 0, 0][..], &[0xc3, 0x28]].concat();
+        assert!(std::io::Cursor::new(&bytes).read_le::<ExtraValue>().is_err());
+
+        // Odd number of bytes for UTF-16
+        let bytes: Vec<u8> = [&[1u8, 3, 0, 0, 0][..], &[b'h', 0, b'i']].concat();
+        assert!(std::io::Cursor::new(&bytes).read_le::<ExtraValue>().is_err());
+
+        let bytes: Vec<u8> = [&[0u8, 2, 0, 0, 0][..], &[0xff, 0xfe]].concat();
+        assert!(std::io::Cursor::new(&bytes).read_le::<KeyDataValue>().is_err());
+    }
+
+    #[test]
+    pub fn name_too_long() {
+        let extra = ExtraValue::new_type("a".repeat(256), String::from("Some.Class"));
+
+        let mut buf = std::io::Cursor::new(Vec::new());
+        assert!(extra.write_le(&mut buf).is_err());
+    }
+
+    #[cfg(feature = "json")]
+    #[test]
+    pub fn bundle_options() {
+        let extra = ExtraValue::new(
+            "Unity.ResourceManager, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null",
+            "UnityEngine.ResourceManagement.ResourceProviders.AssetBundleRequestOptions",
+            "{\"m_Hash\":\"\",\"m_Crc\":3735928559,\"m_Timeout\":0,\"m_BundleSize\":20480}",
+        );
+
+        assert_eq!(extra.bundle_crc(), Some(3735928559));
+        assert_eq!(extra.matches_bundle_size(20480), Some(true));
+        assert_eq!(extra.matches_bundle_size(20479), Some(false));
+
+        // Nothing to compare with
+        assert_eq!(ExtraValue::default().matches_bundle_size(20480), None);
+        assert_eq!(ExtraValue::UInt32(20480).bundle_size(), None);
+    }
+}
@@ -0,0 +1,87 @@
+/// Token Addressables replaces with the location of the aa directory at runtime
+pub const RUNTIME_PATH_TOKEN: &str = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}";
+
+/// Get the load path token (e.g. "{UnityEngine.AddressableAssets.Addressables.RuntimePath}") an InternalId starts with.
+/// Addressables versions and custom profiles don't all use the same one, so any leading token between braces is accepted.
+pub fn runtime_token(internal_id: &str) -> Option<&str> {
+    if !internal_id.starts_with('{') {
+        return None;
+    }
+
+    internal_id.find('}').map(|end| &internal_id[..=end])
+}
+
+/// Get the platform folder (e.g. "Switch", "StandaloneWindows64") of an InternalId located in the RuntimePath
+pub fn platform_of(internal_id: &str) -> Option<&str> {
+    internal_id
+        .strip_prefix(runtime_token(internal_id)?)?
+        .trim_start_matches('/')
+        .split('/')
+        .next()
+        .filter(|platform| !platform.is_empty())
+}
+
+/// Get the path of a bundle relative to the aa directory from its InternalId.
+/// If a platform is provided, it replaces the platform folder the InternalId was built for.
+pub fn relative_bundle_path(internal_id: &str, platform: Option<&str>) -> Option<String> {
+    relative_bundle_path_with_token(internal_id, runtime_token(internal_id)?, platform)
+}
+
+/// Same as [`relative_bundle_path`], but with the load path token provided instead of detected.
+/// Returns None if the InternalId does not start with that token.
+pub fn relative_bundle_path_with_token(internal_id: &str, token: &str, platform: Option<&str>) -> Option<String> {
+    let path = internal_id.strip_prefix(token)?.trim_start_matches('/');
+
+    match platform {
+        Some(platform) => {
+            let (_, remainder) = path.split_once('/')?;
+            Some(format!("{}/{}", platform, remainder))
+        }
+        None => Some(path.to_string()),
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    pub fn switch_layout() {
+        let internal_id = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle";
+
+        assert_eq!(platform_of(internal_id), Some("Switch"));
+        assert_eq!(relative_bundle_path(internal_id, None).unwrap(), "Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle");
+        assert_eq!(relative_bundle_path(internal_id, Some("Switch")).unwrap(), "Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle");
+    }
+
+    #[test]
+    pub fn windows_layout() {
+        let internal_id = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/StandaloneWindows64/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle";
+
+        assert_eq!(platform_of(internal_id), Some("StandaloneWindows64"));
+        assert_eq!(relative_bundle_path(internal_id, None).unwrap(), "StandaloneWindows64/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle");
+        // Switch catalog used against a Windows dump
+        let switch_id = internal_id.replace("StandaloneWindows64", "Switch");
+        assert_eq!(relative_bundle_path(&switch_id, Some("StandaloneWindows64")).unwrap(), "StandaloneWindows64/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle");
+    }
+
+    #[test]
+    pub fn not_in_runtime_path() {
+        let internal_id = "Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535.prefab";
+
+        assert_eq!(platform_of(internal_id), None);
+        assert_eq!(relative_bundle_path(internal_id, None), None);
+    }
+
+    #[test]
+    pub fn custom_token() {
+        let internal_id = "{Game.Content.LoadPath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle";
+
+        assert_eq!(runtime_token(internal_id), Some("{Game.Content.LoadPath}"));
+        assert_eq!(platform_of(internal_id), Some("Switch"));
+        assert_eq!(relative_bundle_path(internal_id, None).unwrap(), "Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle");
+        assert_eq!(relative_bundle_path_with_token(internal_id, "{Game.Content.LoadPath}", Some("StandaloneWindows64")).unwrap(), "StandaloneWindows64/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle");
+        // Overriding with a token the InternalId doesn't use
+        assert_eq!(relative_bundle_path_with_token(internal_id, RUNTIME_PATH_TOKEN, None), None);
+    }
+}
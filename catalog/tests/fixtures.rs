@@ -0,0 +1,138 @@
+use catalog::catalog::Catalog;
+use catalog::lookup::EntryId;
+
+const SHARED_BUNDLE: &str = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/common/shared.bundle";
+const A_BUNDLE: &str = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/a/a.bundle";
+const B_BUNDLE: &str = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/b/b.bundle";
+const A_PREFAB: &str = "Assets/Unit/uBody_A.prefab";
+const B_PREFAB: &str = "Assets/Unit/uBody_B.prefab";
+
+fn fixture_path(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures").join(name)
+}
+
+/// Three bundles and two prefabs, each depending on the shared bundle and its own
+fn fixture() -> Catalog {
+    Catalog::open(fixture_path("catalog.json")).unwrap()
+}
+
+fn entry_id(catalog: &Catalog, internal_id: &str) -> EntryId {
+    catalog.entry_id_of(catalog.get_internal_id_index(internal_id).unwrap()).unwrap()
+}
+
+#[test]
+pub fn open_fixture() {
+    let catalog = fixture();
+
+    assert_eq!(catalog.locator_id(), "AddressablesMainContentCatalog");
+    assert_eq!(catalog.get_internal_ids(), vec![SHARED_BUNDLE, A_BUNDLE, A_PREFAB, B_BUNDLE, B_PREFAB]);
+    assert_eq!(catalog.entry_count(), 5);
+    assert_eq!(catalog.key_count(), 7);
+    assert!(catalog.validate_offsets().is_empty());
+}
+
+#[test]
+pub fn resolve_prefab() {
+    let catalog = fixture();
+
+    let summary = catalog.entry_summary(entry_id(&catalog, A_PREFAB)).unwrap();
+    assert_eq!(summary.internal_id, A_PREFAB);
+    assert_eq!(summary.primary_key.as_deref(), Some("Unit/uBody_A"));
+    assert_eq!(summary.provider.as_deref(), Some("UnityEngine.ResourceManagement.ResourceProviders.BundledAssetProvider"));
+    assert_eq!(summary.resource_type.as_deref(), Some("UnityEngine.GameObject"));
+    assert_eq!(summary.dependencies, vec![SHARED_BUNDLE, A_BUNDLE]);
+    assert!(!summary.is_bundle);
+
+    let b_prefab = catalog.get_entry(entry_id(&catalog, B_PREFAB)).unwrap();
+    assert_eq!(catalog.dependency_hash_of(b_prefab), Some(5678));
+    assert_eq!(catalog.dependencies_recursive(b_prefab), vec![entry_id(&catalog, SHARED_BUNDLE), entry_id(&catalog, B_BUNDLE)]);
+    // Shared bundle (10 bytes) and uBody_B's own (30 bytes)
+    assert_eq!(catalog.recursive_size(b_prefab), 40);
+}
+
+#[test]
+pub fn resolve_bundle_options() {
+    let catalog = fixture();
+
+    let bundle = catalog.get_entry(entry_id(&catalog, A_BUNDLE)).unwrap();
+    let extra = catalog.extra_of(bundle).unwrap();
+    assert_eq!(extra.bundle_crc(), Some(222));
+    assert_eq!(extra.bundle_size(), Some(20));
+
+    assert!(catalog.orphan_bundles().is_empty());
+}
+
+#[test]
+pub fn round_trip_unchanged() {
+    let json = std::fs::read_to_string(fixture_path("catalog.json")).unwrap();
+    let catalog = Catalog::from_str(&json).unwrap();
+
+    assert_eq!(catalog.to_json(false).unwrap(), json);
+    assert_eq!(Catalog::from_str(catalog.to_json(true).unwrap()).unwrap(), catalog);
+}
+
+#[test]
+pub fn open_compressed_fixture() {
+    assert_eq!(Catalog::open(fixture_path("catalog.json.gz")).unwrap(), fixture());
+}
+
+/// The same Catalog stored as the text asset of an uncompressed UnityFS bundle
+#[test]
+pub fn open_bundle_fixture() {
+    assert_eq!(Catalog::from_bundle(fixture_path("catalog.bundle")).unwrap(), fixture());
+
+    let bytes = std::fs::read(fixture_path("catalog.bundle")).unwrap();
+    assert!(catalog::catalog::is_bundle(&bytes));
+    assert_eq!(Catalog::load_auto(&bytes).unwrap(), fixture());
+}
+
+#[test]
+pub fn edit_fixture() {
+    let mut catalog = fixture();
+    catalog.add_bundle("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/c/c.bundle", "fe_assets_unit/model/ubody/c/c.bundle", catalog::lookup::ExtraValue::default()).unwrap();
+    catalog.add_prefab_with_hash("Assets/Unit/uBody_C.prefab", "Unit/uBody_C", &[
+        String::from(SHARED_BUNDLE),
+        String::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/c/c.bundle"),
+    ], 9012).unwrap();
+
+    let reopened = Catalog::from_str(catalog.to_json(false).unwrap()).unwrap();
+    assert!(reopened.validate_offsets().is_empty());
+
+    let prefab = reopened.get_entry(entry_id(&reopened, "Assets/Unit/uBody_C.prefab")).unwrap();
+    assert_eq!(reopened.dependency_hash_of(prefab), Some(9012));
+    assert_eq!(reopened.get_dependencies(prefab).unwrap().len(), 2);
+}
+
+/// Rename every field of a JSON Catalog, including those of its provider data and resource types
+fn rename_fields(value: serde_json::Value, rename: fn(&str) -> String) -> serde_json::Value {
+    match value {
+        serde_json::Value::Object(fields) => fields.into_iter().map(|(name, value)| (rename(&name), rename_fields(value, rename))).collect(),
+        serde_json::Value::Array(values) => values.into_iter().map(|value| rename_fields(value, rename)).collect(),
+        value => value,
+    }
+}
+
+#[test]
+pub fn field_name_variants() {
+    let json: serde_json::Value = serde_json::from_str(&std::fs::read_to_string(fixture_path("catalog.json")).unwrap()).unwrap();
+
+    let pascal_case = |name: &str| {
+        let name = name.trim_start_matches("m_");
+        name[..1].to_uppercase() + &name[1..]
+    };
+    let camel_case = |name: &str| {
+        let name = name.trim_start_matches("m_");
+        name[..1].to_lowercase() + &name[1..]
+    };
+    let prefixed_pascal_case = |name: &str| name.replace("m_resourceTypes", "m_ResourceTypes");
+
+    for rename in [pascal_case as fn(&str) -> String, camel_case, prefixed_pascal_case] {
+        let renamed = rename_fields(json.clone(), rename).to_string();
+        assert!(!renamed.contains("m_resourceTypes"));
+
+        let catalog = Catalog::from_str(&renamed).unwrap();
+        assert_eq!(catalog, fixture());
+        // The Unity names are written back
+        assert_eq!(catalog.to_json(false).unwrap(), std::fs::read_to_string(fixture_path("catalog.json")).unwrap());
+    }
+}
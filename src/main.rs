@@ -1,8 +1,17 @@
-use camino::Utf8PathBuf;
-use catalog::lookup::{ExtraId, KeyDataValue};
+use std::collections::{BTreeMap, BTreeSet, HashSet};
+use std::io::{Read, Write};
+use std::path::{Path, PathBuf};
+
+use camino::{Utf8Path, Utf8PathBuf};
+use catalog::catalog::{is_bundle, is_json, load_catalog, read_catalog, Catalog, CatalogError, EntrySummary, ObjectType};
+use catalog::lookup::{EntryId, EntryValue, ExtraId, ExtraValue, InternalId, KeyDataValue};
+use catalog::runtime_path::relative_bundle_path;
 use dialoguer::{ Select };
+use fuzzy_matcher::skim::SkimMatcherV2;
+use fuzzy_matcher::FuzzyMatcher;
 use serde::{Deserialize, Serialize};
 use structopt::StructOpt;
+use thiserror::Error;
 
 use astra_formats::TextBundle;
 
@@ -12,15 +21,194 @@ use astra_formats::TextBundle;
     about = "Command-line tool to consult and edit a Unity Addressables Catalog"
 )]
 struct Opt {
-    /// Treat the catalog as a bundle
+    /// Treat the catalog as a bundle. Bundles are detected automatically, except when reading from stdin
     #[structopt(short, long)]
     bundled: bool,
-    /// Path to the catalog file as a bundle or a JSON
-    catalog_path: Utf8PathBuf,
+    /// Parse the catalog as a bundle or a JSON regardless of its content. Takes precedence over --bundled
+    #[structopt(long, default_value = "auto", possible_values = InputFormat::VARIANTS)]
+    input_format: InputFormat,
+    /// Only print errors and the requested data, without status messages
+    #[structopt(short, long)]
+    quiet: bool,
+    /// Print additional information such as resolved indices, offsets and selected matches
+    #[structopt(short, long, conflicts_with = "quiet")]
+    verbose: bool,
+    /// Copy the file about to be overwritten to <name>.bak before writing the Catalog
+    #[structopt(long)]
+    backup: bool,
+    /// Overwrite existing backups, and existing output files despite --no-clobber
+    #[structopt(long)]
+    force: bool,
+    /// Refuse to overwrite an existing output file, unless --force is provided
+    #[structopt(long)]
+    no_clobber: bool,
+    /// Write the Catalog JSON pretty-printed instead of compact like Unity does
+    #[structopt(long)]
+    pretty: bool,
+    /// Give new hashes to the dependency keys sharing a hash with different dependencies before writing the Catalog, instead of only warning about them
+    #[structopt(long)]
+    fix_hash_collisions: bool,
+    /// Print errors as a JSON object with the error, its message and context, for tools wrapping this one
+    #[structopt(long)]
+    json_errors: bool,
+    /// Fail with the list of matches instead of prompting when a query matches several InternalIds
+    #[structopt(long)]
+    no_interactive: bool,
+    /// Seed used to generate dependency hashes, so the same edits always produce the same Catalog
+    #[structopt(long)]
+    seed: Option<u64>,
+    /// Path to the catalog file as a bundle or a JSON, or "-" to read it from stdin. Required by every command but completions
+    catalog_path: Option<Utf8PathBuf>,
+    /// Additional JSON shard of the Catalog to load for querying. Can be provided multiple times.
+    #[structopt(long = "catalog", number_of_values = 1)]
+    shards: Vec<Utf8PathBuf>,
     #[structopt(subcommand)]
     cmd: Command,
 }
 
+impl Opt {
+    fn write_options(&self) -> WriteOptions {
+        WriteOptions {
+            backup: self.backup,
+            force: self.force,
+            no_clobber: self.no_clobber,
+            pretty: self.pretty,
+            fix_hash_collisions: self.fix_hash_collisions,
+        }
+    }
+
+    /// Whether the catalog has to be parsed as a bundle
+    fn bundled(&self) -> bool {
+        match self.input_format {
+            InputFormat::Bundle => true,
+            InputFormat::Json => false,
+            // Files that can't be read are left for the command to report
+            InputFormat::Auto => self.bundled || self.catalog_path.as_deref().is_some_and(|path| path != STDIO_PATH && is_bundle_file(path).unwrap_or(false)),
+        }
+    }
+
+    fn verbosity(&self) -> Verbosity {
+        if self.quiet {
+            Verbosity::Quiet
+        } else if self.verbose {
+            Verbosity::Verbose
+        } else {
+            Verbosity::Normal
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+struct WriteOptions {
+    backup: bool,
+    force: bool,
+    no_clobber: bool,
+    pretty: bool,
+    fix_hash_collisions: bool,
+}
+
+impl WriteOptions {
+    /// Make sure the output path may be written to, which is only refused for existing files with --no-clobber
+    fn check_clobber<P: AsRef<Path>>(&self, out_path: P) -> Result<(), AppError> {
+        let out_path = out_path.as_ref();
+
+        if self.no_clobber && !self.force && out_path != Path::new(STDIO_PATH) && out_path.exists() {
+            return Err(AppError::Validation(format!("{} already exists, use --force to overwrite it.", out_path.display())));
+        }
+
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Verbosity {
+    Quiet,
+    Normal,
+    Verbose,
+}
+
+/// How the catalog file is parsed
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum InputFormat {
+    Bundle,
+    Json,
+    /// Detect bundles from their signature
+    Auto,
+}
+
+impl InputFormat {
+    const VARIANTS: &'static [&'static str] = &["bundle", "json", "auto"];
+}
+
+impl std::str::FromStr for InputFormat {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "bundle" => Ok(InputFormat::Bundle),
+            "json" => Ok(InputFormat::Json),
+            "auto" => Ok(InputFormat::Auto),
+            _ => Err(format!("Unknown input format '{}'", s)),
+        }
+    }
+}
+
+/// Order in which listed entries are displayed. The Catalog itself is never reordered.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum SortKey {
+    /// By InternalId
+    Id,
+    /// By class name of the resource type
+    Type,
+    /// By provider index
+    Provider,
+    /// By amount of dependencies
+    Deps,
+}
+
+impl SortKey {
+    const VARIANTS: &'static [&'static str] = &["id", "type", "provider", "deps"];
+}
+
+impl std::str::FromStr for SortKey {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "id" => Ok(SortKey::Id),
+            "type" => Ok(SortKey::Type),
+            "provider" => Ok(SortKey::Provider),
+            "deps" => Ok(SortKey::Deps),
+            _ => Err(format!("Unknown sort key '{}'", s)),
+        }
+    }
+}
+
+/// Print a status message, unless --quiet was provided
+macro_rules! status {
+    ($verbosity:expr, $($arg:tt)*) => {
+        if $verbosity != Verbosity::Quiet {
+            println!($($arg)*);
+        }
+    };
+}
+
+/// Print a warning about the Catalog to stderr, even with --quiet
+macro_rules! warn {
+    ($($arg:tt)*) => {
+        eprintln!("Warning: {}", format!($($arg)*))
+    };
+}
+
+/// Print a diagnostic message, only if --verbose was provided
+macro_rules! verbose {
+    ($verbosity:expr, $($arg:tt)*) => {
+        if $verbosity == Verbosity::Verbose {
+            println!($($arg)*);
+        }
+    };
+}
+
 #[derive(Debug, StructOpt)]
 enum Command {
     /// Append new entries to catalog
@@ -31,13 +219,63 @@ enum Command {
     Extract(Extract),
     /// Output a file addition compliant file for an existing Catalog entry
     Dump(Dump),
+    /// Display the dependency tree of a prefab
+    Tree(Tree),
+    /// Add an existing bundle to the dependencies of an entry
+    AddDependency(AddDependency),
+    /// Remove a bundle from the dependencies of an entry
+    RemoveDependency(RemoveDependency),
+    /// Find the entries whose extra data contains a string
+    Grep(Grep),
+    /// Break down the Catalog entries by resource type and provider
+    Stats(Stats),
+    /// Export the dependency graph as a GraphViz DOT file
+    Graph(Graph),
+    /// Give every prefab a fresh dependency hash
+    Touch(Touch),
+    /// Rewrite the Catalog in the compact form Unity ships, with its counts and key offsets fixed
+    Minify(Minify),
+    /// List the bundles no entry depends on
+    FindOrphans,
+    /// Append a key that no entry uses yet and print its KeyId
+    AddKey(AddKey),
+    /// Replace the start of every InternalId beginning with a prefix
+    Rewrite(Rewrite),
+    /// Check that the bundles needed by prefabs exist in an aa directory and have the size the Catalog records, without copying anything.
+    /// CRCs are not verified.
+    ValidatePaths(ValidatePaths),
+    /// List the InternalIds of the Catalog, one per line
+    ExportIds(ExportIds),
+    /// Compare the bundles needed by a prefab between two aa directories
+    CompareBundles(CompareBundles),
+    /// Check that the key offset of every bucket matches the key table
+    ValidateOffsets(ValidateOffsets),
+    /// Print the raw bytes of an entry, annotated with its fields
+    Hexdump(Hexdump),
+    /// Print the entry index and full InternalId matching a query, or every match with --no-interactive
+    Which(Which),
+    /// List, add or rename the providers entries are loaded with
+    Provider(Provider),
+    /// Write a shell completion script to stdout. The catalog path is ignored
+    #[structopt(setting = structopt::clap::AppSettings::Hidden)]
+    Completions(Completions),
+}
+
+impl Command {
+    fn writes_catalog(&self) -> bool {
+        match self {
+            Command::ValidateOffsets(args) => args.fix.is_some(),
+            Command::Provider(provider) => !matches!(provider, Provider::List),
+            _ => matches!(self, Command::Add(_) | Command::AddDependency(_) | Command::RemoveDependency(_) | Command::Touch(_) | Command::Minify(_) | Command::AddKey(_) | Command::Rewrite(_)),
+        }
+    }
 }
 
 #[derive(Debug, StructOpt)]
 struct Add {
     /// Output path for the catalog file
     out_path: Utf8PathBuf,
-    /// Path to the TOML with the entries to append
+    /// Path to the TOML with the entries to append, or to a directory of TOMLs to append together
     toml_path: Utf8PathBuf,
 }
 
@@ -45,11 +283,14 @@ struct Add {
 struct Dependencies {
     /// InternalId to find dependencies for. Make sure to surround it in quotation marks to not run into trouble.
     internal_id: String,
+    /// Also list the dependencies of the dependencies, each only once
+    #[structopt(short, long)]
+    recursive: bool,
 }
 
 #[derive(Debug, StructOpt)]
 struct Extract {
-    /// Output path for the JSON file
+    /// Output path for the JSON file, or "-" to write it to stdout
     out_path: Utf8PathBuf,
 }
 
@@ -61,368 +302,1966 @@ struct Dump {
     out_path: Utf8PathBuf,
 }
 
+#[derive(Debug, StructOpt)]
+struct Tree {
+    /// InternalId to display the dependency tree of. Make sure to surround it in quotation marks to not run into trouble.
+    internal_id: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct AddDependency {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+    /// InternalId of the entry to add the dependency to. Make sure to surround it in quotation marks to not run into trouble.
+    internal_id: String,
+    /// InternalId of the bundle to depend on. Make sure to surround it in quotation marks to not run into trouble.
+    dependency: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct RemoveDependency {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+    /// InternalId of the entry to remove the dependency from. Make sure to surround it in quotation marks to not run into trouble.
+    internal_id: String,
+    /// InternalId of the bundle to remove. Make sure to surround it in quotation marks to not run into trouble.
+    dependency: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct Grep {
+    /// String to look for in the assembly name, class name and JSON of the extra data
+    pattern: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct Stats {
+    /// Output the statistics as JSON
+    #[structopt(long)]
+    json: bool,
+}
+
+#[derive(Debug, StructOpt)]
+struct Graph {
+    /// Output path for the DOT file
+    out_path: Utf8PathBuf,
+    /// Only export the dependencies of this InternalId, recursively. Make sure to surround it in quotation marks to not run into trouble.
+    internal_id: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+struct Touch {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+struct Minify {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+}
+
+#[derive(Debug, StructOpt)]
+struct AddKey {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+    /// String of the key to add
+    #[structopt(required_unless = "hash", conflicts_with = "hash")]
+    string: Option<String>,
+    /// Add a hash key instead of a string key. It must not already be used by the Catalog
+    #[structopt(long, allow_hyphen_values = true)]
+    hash: Option<i32>,
+}
+
+#[derive(Debug, StructOpt)]
+struct Rewrite {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+    /// Prefix to strip from the InternalIds. Primary keys starting with it are rewritten too
+    from_prefix: String,
+    /// Prefix to put in its place
+    to_prefix: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct ValidatePaths {
+    /// Path to the aa directory holding the platform folders
+    aa_path: Utf8PathBuf,
+    /// InternalIds of the prefabs to check. Make sure to surround them in quotation marks to not run into trouble.
+    #[structopt(required = true)]
+    internal_ids: Vec<String>,
+    /// Platform folder to look into instead of the one the Catalog was built for
+    #[structopt(long)]
+    platform: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+struct CompareBundles {
+    /// Path to the aa directory to check
+    aa_path: Utf8PathBuf,
+    /// Path to the aa directory to compare against
+    reference_path: Utf8PathBuf,
+    /// InternalId of the prefab whose bundles are compared. Make sure to surround it in quotation marks to not run into trouble.
+    internal_id: String,
+    /// Platform folder to look into instead of the one the Catalog was built for
+    #[structopt(long)]
+    platform: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+struct ExportIds {
+    /// Output path for the list. Written to stdout if left out or "-"
+    #[structopt(short, long)]
+    out_path: Option<Utf8PathBuf>,
+    /// Only list the InternalIds containing this string
+    #[structopt(long)]
+    filter: Option<String>,
+    /// Only list the entries whose resource type has this class name, such as UnityEngine.GameObject
+    #[structopt(long = "type")]
+    resource_type: Option<String>,
+    /// Only list the prefabs
+    #[structopt(long, conflicts_with = "bundles-only")]
+    prefabs_only: bool,
+    /// Only list the bundles
+    #[structopt(long)]
+    bundles_only: bool,
+    /// Sort the list instead of following the order of the Catalog
+    #[structopt(long, possible_values = SortKey::VARIANTS)]
+    sort: Option<SortKey>,
+}
+
+#[derive(Debug, StructOpt)]
+struct ValidateOffsets {
+    /// Rewrite the wrong offsets and save the Catalog to this path
+    #[structopt(long, value_name = "out-path")]
+    fix: Option<Utf8PathBuf>,
+}
+
+#[derive(Debug, StructOpt)]
+struct Hexdump {
+    /// InternalId of the entry to dump. Make sure to surround it in quotation marks to not run into trouble.
+    internal_id: String,
+    /// Also dump the primary key of the entry
+    #[structopt(long)]
+    key: bool,
+    /// Also dump the extra data of the entry
+    #[structopt(long)]
+    extra: bool,
+}
+
+#[derive(Debug, StructOpt)]
+struct Which {
+    /// Full or partial InternalId to look for. Make sure to surround it in quotation marks to not run into trouble.
+    query: String,
+}
+
+#[derive(Debug, StructOpt)]
+enum Provider {
+    /// List the provider ids along with the index entries refer to them by
+    List,
+    /// Append a provider and print its index
+    Add(AddProvider),
+    /// Change the id of a provider. Entries keep using it.
+    Rename(RenameProvider),
+}
+
+#[derive(Debug, StructOpt)]
+struct AddProvider {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+    /// Id of the provider, usually its full class name
+    id: String,
+    /// Assembly holding the provider class, such as "Unity.ResourceManager, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null"
+    assembly_name: String,
+    /// Full name of the provider class. Defaults to the id
+    class_name: Option<String>,
+}
+
+#[derive(Debug, StructOpt)]
+struct RenameProvider {
+    /// Output path for the catalog file
+    out_path: Utf8PathBuf,
+    /// Current id of the provider
+    id: String,
+    /// New id of the provider
+    new_id: String,
+}
+
+#[derive(Debug, StructOpt)]
+struct Completions {
+    /// Shell to generate the completion script for
+    #[structopt(possible_values = &structopt::clap::Shell::variants(), case_insensitive = true)]
+    shell: structopt::clap::Shell,
+}
+
 #[derive(Deserialize, Serialize)]
 pub struct CatalogEntries {
+    /// Either list can be left out, so that per-asset TOMLs only need the one they use
+    #[serde(default)]
     bundles: Vec<ExtraBundles>,
+    #[serde(default)]
     prefabs: Vec<ExtraPrefabs>,
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct ExtraBundles {
     internal_id: String,
+    #[serde(default)]
     internal_path: String,
+    /// Primary key of a dumped entry keyed by a hash rather than a path, in which case internal_path is left empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primary_key_hash: Option<i32>,
+    /// Extra data of the bundle. Anything left out is copied from an existing bundle.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    assembly_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    class_name: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    json_text: Option<String>,
+}
+
+impl ExtraBundles {
+    pub fn new<S: Into<String>>(internal_id: S, internal_path: S, extra: Option<&ExtraValue>) -> Self {
+        ExtraBundles {
+            internal_id: internal_id.into(),
+            internal_path: internal_path.into(),
+            primary_key_hash: None,
+            assembly_name: extra.and_then(ExtraValue::assembly_name).map(String::from),
+            class_name: extra.and_then(ExtraValue::class_name).map(String::from),
+            json_text: extra.and_then(ExtraValue::json_text).map(String::from),
+        }
+    }
+
+    fn has_extra(&self) -> bool {
+        self.assembly_name.is_some() || self.class_name.is_some() || self.json_text.is_some()
+    }
+
+    /// Build the extra data for this bundle, filling the gaps with the template
+    fn extra(&self, template: &ExtraValue) -> ExtraValue {
+        ExtraValue::new(
+            self.assembly_name.as_deref().or(template.assembly_name()).unwrap_or_default(),
+            self.class_name.as_deref().or(template.class_name()).unwrap_or_default(),
+            self.json_text.as_deref().or(template.json_text()).unwrap_or_default(),
+        )
+    }
 }
 
 #[derive(Deserialize, Serialize)]
 pub struct ExtraPrefabs {
     internal_id: String,
+    #[serde(default)]
     internal_path: String,
+    /// Primary key of a dumped entry keyed by a hash rather than a path, in which case internal_path is left empty
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    primary_key_hash: Option<i32>,
     dependencies: Vec<String>,
+    /// Dependency hash to use instead of a random one, to keep references from other Catalogs intact
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    hash: Option<i32>,
+}
+
+#[derive(Debug, Default, Serialize)]
+pub struct CatalogStats {
+    entries: usize,
+    extra_data_bytes: u32,
+    resource_types: BTreeMap<String, usize>,
+    providers: BTreeMap<String, usize>,
+    /// Amount of prefabs for each number of dependencies
+    prefab_dependencies: BTreeMap<usize, usize>,
+}
+
+impl CatalogStats {
+    pub fn new(catalog: &Catalog) -> Self {
+        let mut stats = CatalogStats {
+            entries: catalog.entry_count(),
+            extra_data_bytes: catalog.extra_data_size(),
+            ..Default::default()
+        };
+
+        for (resource_type, count) in catalog.resource_type_histogram() {
+            if count != 0 {
+                *stats.resource_types.entry(resource_type).or_default() += count;
+            }
+        }
+
+        // Whatever is left points past the resource types
+        let unknown = stats.entries - stats.resource_types.values().sum::<usize>();
+        if unknown != 0 {
+            stats.resource_types.insert(String::from("<unknown>"), unknown);
+        }
+
+        for entry in catalog.get_entries() {
+            let provider = catalog.provider_of(entry).unwrap_or("<unknown>");
+            *stats.providers.entry(provider.to_string()).or_default() += 1;
+
+            // If 0, we're dealing with a bundle
+            if entry.dependency_hash != 0 {
+                let dependencies = catalog.get_dependencies(entry).map_or(0, |deps| deps.len());
+                *stats.prefab_dependencies.entry(dependencies).or_default() += 1;
+            }
+        }
+
+        stats
+    }
+}
+
+#[derive(Debug, Error)]
+pub enum AppError {
+    #[error("An error happened while processing the Catalog: {0}")]
+    Catalog(#[from] CatalogError),
+    #[error("An error happened while accessing a file: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("An error happened while trying to write the JSON: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("An error happened while trying to read the TOML: {0}")]
+    TomlRead(#[from] serde_toml::de::Error),
+    #[error("An error happened while trying to write the TOML: {0}")]
+    TomlWrite(#[from] serde_toml::ser::Error),
+    #[error("Couldn't find the index for the InternalId '{0}'. Make sure you've got the spelling right.")]
+    MissingInternalId(String),
+    #[error("Multiple InternalIds match '{0}', refine your search:\n{}", .1.join("\n"))]
+    AmbiguousInternalId(String, Vec<String>),
+    #[error("{0}")]
+    Validation(String),
+}
+
+impl AppError {
+    /// Exit code reported to the shell for this error.
+    /// 2: IO, 3: parsing, 4: missing or ambiguous InternalId, 5: validation
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            AppError::Io(_) => 2,
+            AppError::Json(_) | AppError::TomlRead(_) | AppError::TomlWrite(_) => 3,
+            AppError::MissingInternalId(_) | AppError::AmbiguousInternalId(..) => 4,
+            AppError::Validation(_) => 5,
+            AppError::Catalog(err) => match err {
+                CatalogError::Io(_) | CatalogError::Bundle(_) => 2,
+                CatalogError::Json(_) | CatalogError::Base64Decode(_) | CatalogError::UnsupportedVersion(_) => 3,
+                CatalogError::MissingInternalId(_) | CatalogError::MissingEntry | CatalogError::MissingKey => 4,
+                CatalogError::DuplicateInternalId(_) | CatalogError::TableOverflow | CatalogError::HashKey | CatalogError::DuplicateHash => 5,
+                CatalogError::MissingExtraData | CatalogError::NotADependency | CatalogError::DuplicateProvider(_) | CatalogError::MissingProvider(_) => 5,
+                // Errors added to the library since, which still get printed like the others
+                _ => 5,
+            },
+        }
+    }
+
+    /// Name of the error for tools reading the JSON errors. Catalog errors are named after their own variant.
+    fn name(&self) -> &'static str {
+        match self {
+            AppError::Io(_) => "Io",
+            AppError::Json(_) => "Json",
+            AppError::TomlRead(_) => "TomlRead",
+            AppError::TomlWrite(_) => "TomlWrite",
+            AppError::MissingInternalId(_) => "MissingInternalId",
+            AppError::AmbiguousInternalId(..) => "AmbiguousInternalId",
+            AppError::Validation(_) => "Validation",
+            AppError::Catalog(err) => match err {
+                CatalogError::Io(_) => "Io",
+                CatalogError::Json(_) => "Json",
+                CatalogError::Base64Decode(_) => "Base64Decode",
+                CatalogError::Bundle(_) => "Bundle",
+                CatalogError::DuplicateInternalId(_) => "DuplicateInternalId",
+                CatalogError::MissingInternalId(_) => "MissingInternalId",
+                CatalogError::MissingEntry => "MissingEntry",
+                CatalogError::MissingKey => "MissingKey",
+                CatalogError::TableOverflow => "TableOverflow",
+                CatalogError::HashKey => "HashKey",
+                CatalogError::DuplicateHash => "DuplicateHash",
+                CatalogError::MissingExtraData => "MissingExtraData",
+                CatalogError::NotADependency => "NotADependency",
+                CatalogError::DuplicateProvider(_) => "DuplicateProvider",
+                CatalogError::MissingProvider(_) => "MissingProvider",
+                CatalogError::UnsupportedVersion(_) => "UnsupportedVersion",
+                _ => "Catalog",
+            },
+        }
+    }
+
+    /// The error as printed with --json-errors: `{ "error": <name>, "message": <message>, "context": {...} }`
+    pub fn to_json(&self) -> serde_json::Value {
+        let context = match self {
+            AppError::Io(err) | AppError::Catalog(CatalogError::Io(err)) => serde_json::json!({ "kind": format!("{:?}", err.kind()) }),
+            AppError::MissingInternalId(internal_id)
+            | AppError::Catalog(CatalogError::DuplicateInternalId(internal_id))
+            | AppError::Catalog(CatalogError::MissingInternalId(internal_id)) => serde_json::json!({ "internal_id": internal_id }),
+            AppError::AmbiguousInternalId(query, matches) => serde_json::json!({ "query": query, "matches": matches }),
+            _ => serde_json::json!({}),
+        };
+
+        serde_json::json!({
+            "error": self.name(),
+            "message": self.to_string(),
+            "context": context,
+        })
+    }
 }
 
 fn main() {
     let opt = Opt::from_args_safe().unwrap_or_else(|err| {
-        println!("{}", err);
+        eprintln!("{}", err);
         std::process::exit(1);
     });
 
-    match opt.cmd {
-        Command::Add(args) => {
-            // Get a Catalog instance depending on the opening method
-            let res = if opt.bundled {
-                let mut bundle = TextBundle::load(&opt.catalog_path).unwrap();
+    // RUST_LOG takes precedence, so library logs can be tweaked further when needed
+    let log_level = if opt.verbose { "debug" } else { "warn" };
+    env_logger::Builder::from_env(env_logger::Env::default().default_filter_or(log_level)).init();
 
-                catalog::catalog::Catalog::from_str(bundle.take_string().unwrap())
-            } else {
-                catalog::catalog::Catalog::open(&opt.catalog_path)
-            };
+    let json_errors = opt.json_errors;
 
-            // Check for any obvious error
-            let mut catalog = match res {
-                Ok(val) => val,
-                Err(err) => {
-                    match err {
-                        catalog::catalog::CatalogError::Io(io) => {
-                            println!("An error happened while trying to open the Catalog: {}", io)
-                        }
-                        catalog::catalog::CatalogError::Json(json) => {
-                            println!("An error happened while trying to read the JSON: {}", json)
-                        }
-                        _ => (),
-                    }
+    if let Err(err) = run(opt) {
+        if json_errors {
+            eprintln!("{}", err.to_json());
+        } else {
+            eprintln!("{}", err);
+        }
 
-                    std::process::exit(1);
-                }
-            };
+        std::process::exit(err.exit_code());
+    }
+}
 
-            // Get the entries to add from the provided json
-            let entries: CatalogEntries =
-                serde_toml::from_str(&std::fs::read_to_string(args.toml_path).unwrap()).unwrap();
-
-            // We're being lazy here and just getting a copy of an existing metadata for the entries we're about to add
-            let extra = catalog
-                .get_extra(ExtraId(200))
-                .expect("Couldn't get ExtraId")
-                .to_owned();
-
-            // Add bundle entries beforehand, as prefab entries will most likely depend on them.
-            entries.bundles.iter().for_each(|bundle| {
-                catalog
-                    .add_bundle(
-                        bundle.internal_id.to_owned(),
-                        bundle.internal_path.to_owned(),
-                        extra.clone(),
-                    )
-                    .unwrap();
-            });
-
-            // Add prefab entries
-            entries.prefabs.iter().for_each(|prefab| {
-                catalog
-                    .add_prefab(
-                        prefab.internal_id.to_owned(),
-                        prefab.internal_path.to_owned(),
-                        &prefab.dependencies,
-                    )
-                    .unwrap();
-            });
+/// Keep the InternalIds fuzzy matching the query, sorted from best to worst match. Shorter paths come first on equal scores.
+fn rank_internal_ids(internal_ids: Vec<String>, query: &str) -> Vec<String> {
+    let matcher = SkimMatcherV2::default().ignore_case();
 
-            // Save the file to the output path
-            if opt.bundled {
-                let mut bundle = TextBundle::load(&opt.catalog_path).unwrap();
-                bundle
-                    .replace_string(serde_json::to_string(&catalog).unwrap())
-                    .unwrap();
-                bundle.save(args.out_path).unwrap();
+    let mut scored: Vec<(i64, String)> = internal_ids
+        .into_iter()
+        .filter_map(|id| matcher.fuzzy_match(&id, query).map(|score| (score, id)))
+        .collect();
+
+    scored.sort_by(|(a_score, a_id), (b_score, b_id)| b_score.cmp(a_score).then(a_id.len().cmp(&b_id.len())));
+
+    scored.into_iter().map(|(_, id)| id).collect()
+}
+
+/// Find the InternalId index matching the query, prompting the user to pick one if the query is only a partial match.
+/// When not interactive, several matches are an error instead.
+fn resolve_internal_id(catalog: &Catalog, query: &str, interactive: bool, verbosity: Verbosity) -> Result<InternalId, AppError> {
+    let internal_id = match catalog.get_internal_id_index(query) {
+        Some(id) => id,
+        None => {
+            let search = rank_internal_ids(catalog.get_internal_ids(), query);
+
+            if search.is_empty() {
+                return Err(AppError::MissingInternalId(query.to_string()));
+            }
+
+            if !interactive && search.len() > 1 {
+                return Err(AppError::AmbiguousInternalId(query.to_string(), search));
+            }
+
+            let selection = if interactive {
+                dialoguer::FuzzySelect::new()
+                    .with_prompt(
+                        "Multiple InternalIds matching your input have been found, pick one or refine your search",
+                    )
+                    .items(&search)
+                    .interact()?
             } else {
-                std::fs::write(args.out_path, serde_json::to_string(&catalog).unwrap()).unwrap();
+                0
             };
+            verbose!(verbosity, "Selected InternalId: {}", search[selection]);
+            catalog.get_internal_id_index(&search[selection]).ok_or_else(|| AppError::MissingInternalId(search[selection].clone()))?
         }
-        Command::Dependencies(args) => {
-            let res = if opt.bundled {
-                let mut bundle = TextBundle::load(&opt.catalog_path).unwrap();
+    };
 
-                catalog::catalog::Catalog::from_str(bundle.take_string().unwrap())
-            } else {
-                catalog::catalog::Catalog::open(opt.catalog_path)
-            };
+    verbose!(verbosity, "Resolved InternalId index: {}", internal_id.0);
 
-            let catalog = match res {
-                Ok(val) => val,
-                Err(err) => {
-                    match err {
-                        catalog::catalog::CatalogError::Io(io) => {
-                            println!("An error happened while trying to open the Catalog: {}", io)
-                        }
-                        catalog::catalog::CatalogError::Json(json) => {
-                            println!("An error happened while trying to read the JSON: {}", json)
-                        }
-                        _ => (),
-                    }
+    Ok(internal_id)
+}
 
-                    std::process::exit(1);
-                }
-            };
+/// Find the entries matching a query, along with their full InternalId.
+/// An exact match or the pick of the user is the only one, but every match is kept when not interactive.
+fn which(catalog: &Catalog, query: &str, interactive: bool, verbosity: Verbosity) -> Result<Vec<(EntryId, String)>, AppError> {
+    let internal_ids = match catalog.get_internal_id_index(query) {
+        Some(_) => vec![query.to_string()],
+        None if !interactive => rank_internal_ids(catalog.get_internal_ids(), query),
+        None => {
+            let internal_id = resolve_internal_id(catalog, query, interactive, verbosity)?;
+            vec![catalog.get_internal_id_from_index(internal_id).unwrap_or_default().into_owned()]
+        }
+    };
 
-            let internal_id = match catalog.get_internal_id_index(&args.internal_id) {
-                Some(id) => id,
-                None => {
-                    let search: Vec<&String> = catalog
-                        .m_InternalIds
-                        .iter()
-                        .filter(|id| id.contains(&args.internal_id))
-                        .collect();
-
-                    if search.is_empty() {
-                        println!("Couldn't find the index for this InternalId. Make sure you've got the spelling right.");
-                        std::process::exit(1);
-                        unreachable!()
-                    } else {
-                        let selection = dialoguer::FuzzySelect::new()
-                            .with_prompt(
-                                "Multiple InternalIds matching your input have been found, pick one or refine your search",
-                            )
-                            .items(&search)
-                            .interact()
-                            .unwrap();
-                        catalog.get_internal_id_index(search[selection]).unwrap()
-                    }
-                }
-            };
+    if internal_ids.is_empty() {
+        return Err(AppError::MissingInternalId(query.to_string()));
+    }
 
-            let entry = catalog
-                .get_entry_by_internal_id(internal_id)
-                .expect("No entry found for this InternalId. Is the file corrupted?");
-
-            let dependencies = catalog
-                .get_dependencies(entry)
-                .expect("No dependency found for this InternalId. Are you sure this is a prefab?");
-
-            dependencies.iter().for_each(|id| {
-                println!(
-                    "Dependency found: {}",
-                    catalog
-                        .get_internal_id_from_index(catalog.get_entry(*id).unwrap().internal_id)
-                        .unwrap()
-                )
-            });
-        }
-        Command::Extract(args) => {
-            let mut bundle = match TextBundle::load(&opt.catalog_path) {
-                Ok(bundle) => bundle,
-                Err(err) => {
-                    println!("Couldn't not open the bundle file: {}", err);
-                    std::process::exit(1);
-                }
-            };
+    internal_ids
+        .into_iter()
+        .map(|internal_id| {
+            let index = catalog.get_internal_id_index(&internal_id).ok_or_else(|| AppError::MissingInternalId(internal_id.clone()))?;
+            Ok((entry_id_of(catalog, index)?, internal_id))
+        })
+        .collect()
+}
 
-            std::fs::write(args.out_path, bundle.take_string().unwrap()).unwrap();
-        },
-        Command::Dump(args) => {
-            // Get a Catalog instance depending on the opening method
-            let res = if opt.bundled {
-                let mut bundle = TextBundle::load(&opt.catalog_path).unwrap();
+/// Find the entry of an InternalId, naming the InternalId if the Catalog has none for it
+fn entry_id_of(catalog: &Catalog, internal_id: InternalId) -> Result<EntryId, AppError> {
+    catalog.entry_id_of(internal_id).ok_or_else(|| {
+        let name = catalog.get_internal_id_from_index(internal_id).unwrap_or_default();
+        AppError::Validation(format!("No entry found for the InternalId '{}'. Is the file corrupted?", name))
+    })
+}
 
-                catalog::catalog::Catalog::from_str(bundle.take_string().unwrap())
-            } else {
-                catalog::catalog::Catalog::open(&opt.catalog_path)
-            };
+/// Get the InternalId of an entry, naming the entry if it can't be resolved
+fn entry_internal_id(catalog: &Catalog, entry_id: EntryId) -> Result<String, AppError> {
+    catalog
+        .get_entry(entry_id)
+        .and_then(|entry| catalog.get_internal_id_from_index(entry.internal_id))
+        .map(|internal_id| internal_id.into_owned())
+        .ok_or_else(|| AppError::Validation(format!("Entry {} is missing or points to a missing InternalId. Is the file corrupted?", entry_id.0)))
+}
 
-            // Check for any obvious error
-            let mut catalog = match res {
-                Ok(val) => val,
-                Err(err) => {
-                    match err {
-                        catalog::catalog::CatalogError::Io(io) => {
-                            println!("An error happened while trying to open the Catalog: {}", io)
-                        }
-                        catalog::catalog::CatalogError::Json(json) => {
-                            println!("An error happened while trying to read the JSON: {}", json)
-                        }
-                        _ => (),
-                    }
+/// Render the dependencies of an entry as an ASCII tree, one line per dependency.
+/// Entries that were already expanded elsewhere in the tree are marked as shared instead of being expanded again.
+fn write_dependency_tree(catalog: &Catalog, entry_id: EntryId, prefix: &str, visited: &mut HashSet<EntryId>, lines: &mut Vec<String>) -> Result<(), AppError> {
+    let entry = catalog
+        .get_entry(entry_id)
+        .ok_or_else(|| AppError::Validation(format!("Entry {} is missing. Is the file corrupted?", entry_id.0)))?;
 
-                    std::process::exit(1);
-                }
-            };
+    let dependencies = catalog.get_dependencies(entry).unwrap_or_default();
 
-            let internal_id = match catalog.get_internal_id_index(&args.internal_id) {
-                Some(id) => id,
-                None => {
-                    let search: Vec<&String> = catalog
-                        .m_InternalIds
-                        .iter()
-                        .filter(|id| id.contains(&args.internal_id))
-                        .collect();
-
-                    if search.is_empty() {
-                        println!("Couldn't find the index for this InternalId. Make sure you've got the spelling right.");
-                        std::process::exit(1);
-                        unreachable!()
-                    } else {
-                        let selection = dialoguer::FuzzySelect::new()
-                            .with_prompt(
-                                "Multiple InternalIds matching your input have been found, pick one or refine your search",
-                            )
-                            .items(&search)
-                            .interact()
-                            .unwrap();
-                        catalog.get_internal_id_index(search[selection]).unwrap()
-                    }
-                }
-            };
+    for (index, dependency) in dependencies.iter().enumerate() {
+        let (branch, indent) = if index == dependencies.len() - 1 { ("└─ ", "   ") } else { ("├─ ", "│  ") };
+
+        let name = entry_internal_id(catalog, *dependency)?;
 
+        if visited.insert(*dependency) {
+            lines.push(format!("{}{}{}", prefix, branch, name));
+            write_dependency_tree(catalog, *dependency, &format!("{}{}", prefix, indent), visited, lines)?;
+        } else {
+            lines.push(format!("{}{}{} (shared)", prefix, branch, name));
+        }
+    }
+
+    Ok(())
+}
+
+/// Render the dependency graph as GraphViz DOT, either for the whole Catalog or for everything reachable from an entry
+fn dependency_graph(catalog: &Catalog, root: Option<EntryId>) -> Result<String, AppError> {
+    let nodes: BTreeSet<EntryId> = match root {
+        Some(root) => {
             let entry = catalog
-                .get_entry_by_internal_id(internal_id)
-                .expect("No entry found for this InternalId. Is the file corrupted?");
-
-            let internal_path = match catalog.get_key(entry.primary_key).expect("Couldn't get the KeyDataValue???") {
-                KeyDataValue::String { string, .. } => Some(string),
-                KeyDataValue::Hash(_) => None,
-            }.expect("KeyDataValue is of type Hash. Is the file corrupted?");
-
-            // TODO: Add CatalogEntries::new()
-            let mut entries = CatalogEntries {
-                bundles: vec![],
-                prefabs: vec![],
-            };
+                .get_entry(root)
+                .ok_or_else(|| AppError::Validation(format!("Entry {} is missing. Is the file corrupted?", root.0)))?;
+            std::iter::once(root).chain(catalog.iter_dependencies_recursive(entry)).collect()
+        }
+        None => (0..catalog.get_entries().len()).map(EntryId::from).collect(),
+    };
 
-            let id = catalog.get_internal_id_from_index(internal_id).unwrap();
+    let mut lines = vec![String::from("digraph catalog {"), String::from("    rankdir=LR;")];
 
-            // If 0, we're dealing with a bundle
-            if entry.dependency_hash == 0 {
-                entries.bundles.push(ExtraBundles { internal_id: id.to_owned(), internal_path: internal_path.to_string() })
-            } else {
-                let deps = catalog
-                .get_dependencies(entry)
-                .expect("No dependency found for this InternalId. Are you sure this is a prefab?");
-
-                let dependencies = deps.iter().map(|id| {
-                        catalog
-                            .get_internal_id_from_index(catalog.get_entry(*id).unwrap().internal_id)
-                            .unwrap().to_owned()
-                }).collect();
-
-                // Just in case
-                if !deps.is_empty() {
-                    let bundle_entry = catalog.get_entry(deps[0]).unwrap();
-
-                    let bundle_id = catalog.get_internal_id_from_index(bundle_entry.internal_id).unwrap();
-                    let bundle_path = match catalog.get_key(bundle_entry.primary_key).expect("Couldn't get the KeyDataValue???") {
-                        KeyDataValue::String { string, .. } => Some(string),
-                        KeyDataValue::Hash(_) => None,
-                    }.expect("KeyDataValue is of type Hash. Is the file corrupted?");
-                    entries.bundles.push(ExtraBundles { internal_id: bundle_id.to_owned(), internal_path: bundle_path.to_string() })
-                }
+    for entry_id in nodes.iter() {
+        let summary = catalog
+            .entry_summary(*entry_id)
+            .ok_or_else(|| AppError::Validation(format!("Entry {} or one of its dependencies is missing. Is the file corrupted?", entry_id.0)))?;
 
-                entries.prefabs.push(ExtraPrefabs {
-                    internal_id: id.to_owned(),
-                    internal_path: internal_path.to_string(),
-                    dependencies
-                })
-            }
+        let style = if summary.is_bundle { "shape=box, style=filled, fillcolor=lightgrey" } else { "shape=ellipse" };
+        let label = summary.internal_id.replace('\\', "\\\\").replace('"', "\\\"");
+        lines.push(format!("    e{} [label=\"{}\", {}];", entry_id.0, label, style));
+    }
 
-            std::fs::write(args.out_path, serde_toml::to_string_pretty(&entries).unwrap()).unwrap();
-            println!("Entry exported successfully.");
+    for entry_id in nodes.iter() {
+        let entry = catalog
+            .get_entry(*entry_id)
+            .ok_or_else(|| AppError::Validation(format!("Entry {} is missing. Is the file corrupted?", entry_id.0)))?;
+
+        for dependency in catalog.get_dependencies(entry).unwrap_or_default() {
+            lines.push(format!("    e{} -> e{};", entry_id.0, dependency.0));
         }
     }
+
+    lines.push(String::from("}"));
+
+    Ok(lines.join("\n"))
 }
 
-// TODO: Move this to library
-// TODO: Write actual tests
-#[cfg(test)]
-mod test {
-    use catalog::lookup::KeyDataValue;
-
-    use crate::{CatalogEntries, ExtraBundles, ExtraPrefabs};
-
-    // #[test]
-    // pub fn edit_test() {
-    //     let catalog = catalog::catalog::Catalog::open("./catalog_edit.json").unwrap();
-    //     let bundle_id = catalog.get_internal_id_index("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle").unwrap();
-    //     let bundle_entry = dbg!(catalog.get_entry_by_internal_id(bundle_id).unwrap());
-    //     let bundle_key = dbg!(catalog.get_key(bundle_entry.primary_key).unwrap());
-    //     let bundle_bucket = dbg!(catalog.get_bucket(bundle_entry.primary_key).unwrap());
-    //     let bundle_entry_id = dbg!(catalog.get_entry_id_by_internal_id(bundle_id).unwrap());
-
-    //     let prefab_id = catalog.get_internal_id_index("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab").unwrap();
-    //     let prefab_entry = dbg!(catalog.get_entry_by_internal_id(prefab_id).unwrap());
-    //     let prefab_key = dbg!(catalog.get_key(prefab_entry.primary_key).unwrap());
-    //     let prefab_bucket = dbg!(catalog.get_bucket(prefab_entry.primary_key).unwrap());
-    //     let prefab_entry_id = dbg!(catalog.get_entry_id_by_internal_id(prefab_id).unwrap());
-
-    //     let dependency_key = dbg!(catalog.get_key(prefab_entry.dependency_key_idx).unwrap());
-    //     let dependency_buncket = dbg!(catalog.get_bucket(prefab_entry.dependency_key_idx).unwrap());
-    // }
-
-    // #[test]
-    // pub fn test() {
-    //     let catalog = catalog::catalog::Catalog::open("./catalog.json").unwrap();
-    //     let bundle_id = catalog.get_internal_id_index("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle").unwrap();
-    //     let bundle_entry = dbg!(catalog.get_entry_by_internal_id(bundle_id).unwrap());
-    //     let bundle_key = dbg!(catalog.get_key(bundle_entry.primary_key).unwrap());
-    //     let bundle_bucket = dbg!(catalog.get_bucket(bundle_entry.primary_key).unwrap());
-    //     let bundle_entry_id = dbg!(catalog.get_entry_id_by_internal_id(bundle_id).unwrap());
-
-    //     let prefab_id = catalog.get_internal_id_index("Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535.prefab").unwrap();
-    //     let prefab_entry = dbg!(catalog.get_entry_by_internal_id(prefab_id).unwrap());
-    //     let prefab_key = dbg!(catalog.get_key(prefab_entry.primary_key).unwrap());
-    //     let prefab_bucket = dbg!(catalog.get_bucket(prefab_entry.primary_key).unwrap());
-    //     let prefab_entry_id = dbg!(catalog.get_entry_id_by_internal_id(prefab_id).unwrap());
-
-    //     let dependency_key = dbg!(catalog.get_key(prefab_entry.dependency_key_idx).unwrap());
-    //     let dependency_buncket = dbg!(catalog.get_bucket(prefab_entry.dependency_key_idx).unwrap());
-    // }
+/// Location of a bundle needed by a prefab, and whether it exists in the aa directory
+#[derive(Debug, PartialEq, Eq)]
+struct BundlePath {
+    internal_id: String,
+    /// None if the bundle is not in the RuntimePath, so it can't be looked up
+    path: Option<Utf8PathBuf>,
+    present: bool,
+    /// Whether the file has the size recorded in the extra data of the bundle. None if it is missing or no size is recorded.
+    size_matches: Option<bool>,
+}
 
-    #[test]
-    pub fn output_example_toml() {
-        let entries = CatalogEntries {
-            bundles: vec![
-                ExtraBundles {
-                    internal_id: "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle".to_string(),
-                    internal_path: "fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle".to_string(),
-                },
-                ExtraBundles {
-                    internal_id: "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle".to_string(),
-                    internal_path: "fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle".to_string(),
-                },
+/// Locate every bundle the entries depend on, recursively, in the aa directory. Each bundle is only listed once.
+fn validate_paths(catalog: &Catalog, roots: &[EntryId], aa_path: &Utf8Path, platform: Option<&str>) -> Result<Vec<BundlePath>, AppError> {
+    let mut visited = HashSet::new();
+    let mut bundles = vec![];
+
+    for root in roots {
+        let entry = catalog
+            .get_entry(*root)
+            .ok_or_else(|| AppError::Validation(format!("Entry {} is missing. Is the file corrupted?", root.0)))?;
+
+        for id in catalog.dependencies_recursive(entry) {
+            if !visited.insert(id) {
+                continue;
+            }
+
+            let summary = catalog
+                .entry_summary(id)
+                .ok_or_else(|| AppError::Validation(format!("Entry {} or one of its dependencies is missing. Is the file corrupted?", id.0)))?;
+
+            if !summary.is_bundle {
+                continue;
+            }
+
+            let path = relative_bundle_path(&summary.internal_id, platform).map(|path| aa_path.join(path));
+            let present = path.as_ref().is_some_and(|path| path.is_file());
+
+            let size_matches = match (&path, catalog.get_entry(id).and_then(|entry| catalog.extra_of(entry))) {
+                (Some(path), Some(extra)) if present => extra.matches_bundle_size(std::fs::metadata(path)?.len()),
+                _ => None,
+            };
+
+            bundles.push(BundlePath { internal_id: summary.internal_id, path, present, size_matches });
+        }
+    }
+
+    Ok(bundles)
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum BundleComparison {
+    Identical,
+    Different,
+    /// Missing from the first aa directory, the second one, or both
+    Missing { first: bool, second: bool },
+    /// Not in the RuntimePath, so it can't be looked up
+    Unresolved,
+}
+
+/// Compare the content of every bundle an entry depends on, recursively, between two aa directories
+fn compare_bundles(catalog: &Catalog, root: EntryId, aa_path: &Utf8Path, reference_path: &Utf8Path, platform: Option<&str>) -> Result<Vec<(String, BundleComparison)>, AppError> {
+    let bundles = validate_paths(catalog, &[root], aa_path, platform)?;
+    let references = validate_paths(catalog, &[root], reference_path, platform)?;
+
+    // Both lists follow the dependencies in the same order
+    bundles
+        .into_iter()
+        .zip(references)
+        .map(|(bundle, reference)| {
+            let comparison = match (&bundle.path, &reference.path) {
+                (Some(path), Some(reference_path)) if bundle.present && reference.present => {
+                    if std::fs::read(path)? == std::fs::read(reference_path)? {
+                        BundleComparison::Identical
+                    } else {
+                        BundleComparison::Different
+                    }
+                }
+                (Some(_), Some(_)) => BundleComparison::Missing { first: !bundle.present, second: !reference.present },
+                _ => BundleComparison::Unresolved,
+            };
+
+            Ok((bundle.internal_id, comparison))
+        })
+        .collect()
+}
+
+/// Fields of an entry in the order they are stored, with their size in bytes
+const ENTRY_FIELDS: [(&str, usize); 7] = [
+    ("internal_id", 4),
+    ("provider_index", 4),
+    ("dependency_key_idx", 4),
+    ("dependency_hash", 4),
+    ("data_index", 4),
+    ("primary_key", 4),
+    ("resource_type", 4),
+];
+
+/// Format bytes as hex and ASCII, 16 per line, starting a new line for each field and naming it at the end
+fn hexdump(bytes: &[u8], fields: &[(&str, usize)]) -> Vec<String> {
+    let mut lines = vec![];
+    let mut offset = 0;
+
+    for (name, size) in fields {
+        let field = &bytes[offset.min(bytes.len())..(offset + size).min(bytes.len())];
+
+        for (index, chunk) in field.chunks(16).enumerate() {
+            let hex: Vec<String> = chunk.iter().map(|byte| format!("{:02x}", byte)).collect();
+            let ascii: String = chunk.iter().map(|byte| if byte.is_ascii_graphic() || *byte == b' ' { *byte as char } else { '.' }).collect();
+            let annotation = if index == 0 { *name } else { "" };
+
+            lines.push(format!("{:08x}  {:<47}  |{:<16}|  {}", offset + index * 16, hex.join(" "), ascii, annotation).trim_end().to_string());
+        }
+
+        offset += size;
+    }
+
+    lines
+}
+
+/// Dump the raw bytes of an entry, and optionally those of its primary key and extra data
+fn hexdump_entry(catalog: &Catalog, entry_id: EntryId, key: bool, extra: bool) -> Result<Vec<String>, AppError> {
+    let missing = || AppError::Validation(format!("Entry {} is missing. Is the file corrupted?", entry_id.0));
+
+    let entry = catalog.get_entry(entry_id).ok_or_else(missing)?;
+    let mut lines = vec![format!("Entry {}:", entry_id.0)];
+    lines.extend(hexdump(&catalog.raw_entry(entry_id).ok_or_else(missing)?, &ENTRY_FIELDS));
+
+    if key {
+        let bytes = catalog
+            .raw_key(entry.primary_key)
+            .ok_or_else(|| AppError::Validation(format!("Key {} is missing. Is the file corrupted?", isize::from(entry.primary_key))))?;
+
+        lines.push(format!("Key {}:", isize::from(entry.primary_key)));
+        lines.extend(hexdump(&bytes, &[("primary_key", bytes.len())]));
+    }
+
+    if extra {
+        match catalog.raw_extra(entry) {
+            Some(bytes) => {
+                lines.push(format!("Extra data at {}:", entry.data_index.0));
+                lines.extend(hexdump(&bytes, &[("extra", bytes.len())]));
+            }
+            None => lines.push(String::from("No extra data.")),
+        }
+    }
+
+    Ok(lines)
+}
+
+/// Path standing for stdin when reading and for stdout when writing
+const STDIO_PATH: &str = "-";
+
+/// InternalIds of the entries, in Catalog order unless sorted, keeping those containing the filter.
+/// Bundles or prefabs can be left out, and the entries can be limited to a resource type.
+fn export_internal_ids(catalog: &Catalog, filter: Option<&str>, resource_type: Option<&str>, bundles: bool, prefabs: bool, sort: Option<SortKey>) -> Vec<String> {
+    let of_type: Option<HashSet<EntryId>> = resource_type.map(|class_name| catalog.find_entries_by_type(class_name).into_iter().collect());
+
+    let mut entries: Vec<(&EntryValue, String)> = catalog
+        .get_entries()
+        .iter()
+        .enumerate()
+        .filter(|(index, _)| of_type.as_ref().is_none_or(|of_type| of_type.contains(&EntryId::from(*index))))
+        .map(|(_, entry)| entry)
+        // If 0, we're dealing with a bundle
+        .filter(|entry| if entry.dependency_hash == 0 { bundles } else { prefabs })
+        .filter_map(|entry| Some((entry, catalog.get_internal_id_from_index(entry.internal_id)?.into_owned())))
+        .filter(|(_, internal_id)| filter.is_none_or(|filter| internal_id.contains(filter)))
+        .collect();
+
+    // Sorting is stable, so ties keep the order of the Catalog
+    match sort {
+        Some(SortKey::Id) => entries.sort_by(|(_, a), (_, b)| a.cmp(b)),
+        Some(SortKey::Type) => entries.sort_by_key(|(entry, _)| catalog.resource_type_of(entry).map(|ty| ty.class_name())),
+        Some(SortKey::Provider) => entries.sort_by_key(|(entry, _)| entry.provider_index),
+        Some(SortKey::Deps) => entries.sort_by_key(|(entry, _)| catalog.get_dependencies(entry).map_or(0, |deps| deps.len())),
+        None => (),
+    }
+
+    entries.into_iter().map(|(_, internal_id)| internal_id).collect()
+}
+
+/// Open the Catalog and merge the shards it was split into
+fn open_catalog(catalog_path: &Utf8Path, shards: &[Utf8PathBuf], bundled: bool) -> Result<Catalog, AppError> {
+    let mut catalog = if catalog_path == STDIO_PATH {
+        read_catalog(std::io::stdin().lock(), bundled)?
+    } else {
+        load_catalog(catalog_path, bundled)?
+    };
+
+    for shard in shards {
+        catalog.merge(Catalog::open(shard)?)?;
+    }
+
+    Ok(catalog)
+}
+
+/// Check if a file starts like a Unity bundle rather than a JSON Catalog
+fn is_bundle_file<P: AsRef<Path>>(path: P) -> std::io::Result<bool> {
+    let mut signature = Vec::new();
+    std::fs::File::open(path)?.take(16).read_to_end(&mut signature)?;
+
+    Ok(is_bundle(&signature))
+}
+
+/// Write the JSON of a Catalog bundle to the output path, with "-" standing for stdin and stdout.
+/// Returns true if the input was already a JSON.
+fn extract<P: AsRef<Path>, O: AsRef<Path>>(catalog_path: P, out_path: O, pretty: bool) -> Result<bool, AppError> {
+    let input: Box<dyn Read> = if catalog_path.as_ref() == Path::new(STDIO_PATH) {
+        Box::new(std::io::stdin().lock())
+    } else {
+        Box::new(std::fs::File::open(catalog_path)?)
+    };
+
+    if out_path.as_ref() == Path::new(STDIO_PATH) {
+        extract_stream(input, std::io::stdout().lock(), pretty)
+    } else {
+        extract_stream(input, std::fs::File::create(out_path)?, pretty)
+    }
+}
+
+/// Write the JSON of a Catalog bundle read from the input to the output.
+/// The JSON is copied as is unless it has to be pretty-printed. Returns true if the input was already a JSON.
+fn extract_stream<R: Read, W: Write>(mut input: R, mut output: W, pretty: bool) -> Result<bool, AppError> {
+    let mut data = Vec::new();
+    input.read_to_end(&mut data)?;
+
+    let is_json = is_json(&data);
+
+    let json = if is_json {
+        data
+    } else {
+        let mut bundle = TextBundle::from_slice(&data).map_err(CatalogError::Bundle)?;
+        bundle.take_string().map_err(CatalogError::Bundle)?.into_bytes()
+    };
+
+    if pretty {
+        Catalog::from_slice(json)?.write_to(&mut output, true)?;
+    } else {
+        output.write_all(&json)?;
+    }
+
+    output.flush()?;
+
+    Ok(is_json)
+}
+
+/// Copy a file to <name>.bak, refusing to replace an existing backup unless forced.
+/// Returns the path of the backup, or None if there was no file to back up.
+fn backup_file<P: AsRef<Path>>(path: P, force: bool) -> Result<Option<PathBuf>, AppError> {
+    let path = path.as_ref();
+
+    if !path.exists() {
+        return Ok(None);
+    }
+
+    let mut backup_path = path.as_os_str().to_owned();
+    backup_path.push(".bak");
+    let backup_path = PathBuf::from(backup_path);
+
+    if backup_path.exists() && !force {
+        return Err(AppError::Validation(format!("A backup already exists at {}, use --force to overwrite it.", backup_path.display())));
+    }
+
+    std::fs::copy(path, &backup_path)?;
+
+    Ok(Some(backup_path))
+}
+
+/// Write the Catalog to the output path. If the Catalog was bundled, the original bundle is used as a base for the new one.
+fn save_catalog<P: AsRef<Path>, O: AsRef<Path>>(catalog: &mut Catalog, catalog_path: P, out_path: O, bundled: bool, options: WriteOptions) -> Result<(), AppError> {
+    options.check_clobber(&out_path)?;
+
+    if options.backup {
+        backup_file(&out_path, options.force)?;
+    }
+
+    // Counts that drifted from the content of their table would corrupt the Catalog once serialized
+    catalog.normalize_counts()?;
+    // Unity would resolve all but one of the keys sharing a hash to the wrong dependencies
+    if options.fix_hash_collisions {
+        let fixed = catalog.resolve_hash_collisions();
+
+        if fixed > 0 {
+            warn!("Gave a new dependency hash to {} key(s) colliding with another.", fixed);
+        }
+    } else {
+        for collision in catalog.hash_collisions() {
+            let keys: Vec<String> = collision.keys.iter().map(|key| isize::from(*key).to_string()).collect();
+            warn!("Dependency hash {} is shared by keys {} with different dependencies. Use --fix-hash-collisions to give them new hashes.", collision.hash, keys.join(", "));
+        }
+    }
+    catalog.shrink_to_fit();
+
+    if bundled {
+        let mut bundle = TextBundle::load(catalog_path).map_err(CatalogError::Bundle)?;
+        bundle
+            .replace_string(catalog.to_json(options.pretty)?)
+            .map_err(CatalogError::Bundle)?;
+        bundle.save(out_path).map_err(CatalogError::Bundle)?;
+    } else {
+        catalog.write_to(std::fs::File::create(out_path)?, options.pretty)?;
+    };
+
+    Ok(())
+}
+
+/// Save the Catalog compact regardless of --pretty, after fixing its key offsets. Counts are fixed by save_catalog.
+/// Returns how many key offsets were wrong.
+fn minify<P: AsRef<Path>, O: AsRef<Path>>(catalog: &mut Catalog, catalog_path: P, out_path: O, bundled: bool, options: WriteOptions) -> Result<usize, AppError> {
+    let fixed = catalog.fix_key_offsets();
+
+    save_catalog(catalog, catalog_path, out_path, bundled, WriteOptions { pretty: false, ..options })?;
+
+    Ok(fixed)
+}
+
+/// Read the entries to add from a TOML, or from every TOML in a directory combined.
+/// Files are read in name order, and since bundles are added first, prefabs can depend on bundles from any file.
+fn read_entries(path: &Utf8Path) -> Result<CatalogEntries, AppError> {
+    if !path.is_dir() {
+        return Ok(serde_toml::from_str(&std::fs::read_to_string(path)?)?);
+    }
+
+    let mut toml_paths = path
+        .read_dir_utf8()?
+        .map(|entry| entry.map(|entry| entry.into_path()))
+        .collect::<std::io::Result<Vec<Utf8PathBuf>>>()?;
+    toml_paths.retain(|toml_path| toml_path.extension() == Some("toml") && toml_path.is_file());
+    toml_paths.sort();
+
+    let mut entries = CatalogEntries { bundles: vec![], prefabs: vec![] };
+
+    for toml_path in toml_paths {
+        let file: CatalogEntries = serde_toml::from_str(&std::fs::read_to_string(toml_path)?)?;
+        entries.bundles.extend(file.bundles);
+        entries.prefabs.extend(file.prefabs);
+    }
+
+    Ok(entries)
+}
+
+/// Append the bundles and prefabs described in a TOML to the Catalog
+fn add_entries(catalog: &mut Catalog, entries: &CatalogEntries, verbosity: Verbosity) -> Result<(), AppError> {
+    // We're being lazy here and just getting a copy of an existing metadata for the bundles that don't provide theirs
+    let template = catalog.get_extra(ExtraId(200)).cloned();
+
+    let keyed_by_hash = entries.bundles.iter().filter(|bundle| bundle.primary_key_hash.is_some()).map(|bundle| &bundle.internal_id)
+        .chain(entries.prefabs.iter().filter(|prefab| prefab.primary_key_hash.is_some()).map(|prefab| &prefab.internal_id))
+        .next();
+
+    if let Some(internal_id) = keyed_by_hash {
+        return Err(AppError::Validation(format!("'{}' is keyed by a hash, which can't be added yet. Give it an internal_path instead.", internal_id)));
+    }
+
+    // Add bundle entries beforehand, as prefab entries will most likely depend on them.
+    for bundle in entries.bundles.iter() {
+        let extra = match &template {
+            Some(template) => bundle.extra(template),
+            None if bundle.has_extra() => bundle.extra(&ExtraValue::default()),
+            None => return Err(AppError::Validation(String::from("Couldn't get ExtraId"))),
+        };
+
+        verbose!(verbosity, "Adding bundle {} at key offset {}", bundle.internal_id, catalog.get_next_key_offset());
+        catalog.add_bundle(
+            bundle.internal_id.to_owned(),
+            bundle.internal_path.to_owned(),
+            extra,
+        )?;
+    }
+
+    // Add prefab entries
+    for prefab in entries.prefabs.iter() {
+        verbose!(verbosity, "Adding prefab {} at key offset {}", prefab.internal_id, catalog.get_next_key_offset());
+
+        match prefab.hash {
+            Some(hash) => catalog.add_prefab_with_hash(
+                prefab.internal_id.to_owned(),
+                prefab.internal_path.to_owned(),
+                &prefab.dependencies,
+                hash,
+            )?,
+            None => catalog.add_prefab(
+                prefab.internal_id.to_owned(),
+                prefab.internal_path.to_owned(),
+                &prefab.dependencies,
+            )?,
+        }
+    }
+
+    Ok(())
+}
+
+/// Find the entry of an InternalId and summarize it
+fn summarize(catalog: &Catalog, internal_id: InternalId) -> Result<(&EntryValue, EntrySummary), AppError> {
+    let entry_id = entry_id_of(catalog, internal_id)?;
+
+    let entry = catalog
+        .get_entry(entry_id)
+        .ok_or_else(|| AppError::Validation(format!("Entry {} is missing. Is the file corrupted?", entry_id.0)))?;
+    let summary = catalog.entry_summary(entry_id).ok_or_else(|| {
+        let name = catalog.get_internal_id_from_index(internal_id).unwrap_or_default();
+        AppError::Validation(format!("The entry of '{}' points to a missing key or entry. Is the file corrupted?", name))
+    })?;
+
+    Ok((entry, summary))
+}
+
+/// Get the primary key of a summarized entry as the internal path and hash of the TOML, only one of which is set
+fn dumped_primary_key(catalog: &Catalog, entry: &EntryValue, summary: &EntrySummary) -> Result<(String, Option<i32>), AppError> {
+    match (&summary.primary_key, catalog.get_key(entry.primary_key)) {
+        (Some(internal_path), _) => Ok((internal_path.clone(), None)),
+        (None, Some(KeyDataValue::Hash(hash))) => Ok((String::new(), Some(*hash))),
+        _ => Err(AppError::Validation(format!("The primary key of '{}' is missing. Is the file corrupted?", summary.internal_id))),
+    }
+}
+
+/// Describe an existing entry in the format used by the Add command
+fn dump_entry(catalog: &Catalog, internal_id: InternalId, verbosity: Verbosity) -> Result<CatalogEntries, AppError> {
+    let (entry, summary) = summarize(catalog, internal_id)?;
+
+    verbose!(verbosity, "Entry: {:?}", entry);
+    verbose!(verbosity, "Provider: {}", summary.provider.as_deref().unwrap_or("<unknown>"));
+
+    let (internal_path, primary_key_hash) = dumped_primary_key(catalog, entry, &summary)?;
+
+    // TODO: Add CatalogEntries::new()
+    let mut entries = CatalogEntries {
+        bundles: vec![],
+        prefabs: vec![],
+    };
+
+    if summary.is_bundle {
+        let mut bundle = ExtraBundles::new(summary.internal_id, internal_path, catalog.extra_of(entry));
+        bundle.primary_key_hash = primary_key_hash;
+        entries.bundles.push(bundle)
+    } else {
+        // Just in case
+        if let Some(bundle_id) = summary.dependencies.first() {
+            let bundle_index = catalog.get_internal_id_index(bundle_id).ok_or_else(|| AppError::MissingInternalId(bundle_id.clone()))?;
+            let (bundle_entry, bundle) = summarize(catalog, bundle_index)?;
+            let (bundle_path, bundle_hash) = dumped_primary_key(catalog, bundle_entry, &bundle)?;
+
+            let mut extra_bundle = ExtraBundles::new(bundle.internal_id, bundle_path, catalog.extra_of(bundle_entry));
+            extra_bundle.primary_key_hash = bundle_hash;
+            entries.bundles.push(extra_bundle)
+        }
+
+        entries.prefabs.push(ExtraPrefabs {
+            internal_id: summary.internal_id,
+            internal_path,
+            primary_key_hash,
+            dependencies: summary.dependencies,
+            hash: None,
+        })
+    }
+
+    Ok(entries)
+}
+
+fn run(opt: Opt) -> Result<(), AppError> {
+    let verbosity = opt.verbosity();
+    let write_options = opt.write_options();
+    let interactive = !opt.no_interactive;
+
+    // Completions don't read the Catalog, so they are generated before requiring its path
+    if let Command::Completions(args) = &opt.cmd {
+        Opt::clap().gen_completions_to(env!("CARGO_PKG_NAME"), args.shell, &mut std::io::stdout());
+        return Ok(());
+    }
+
+    let catalog_path = opt.catalog_path.as_deref().ok_or_else(|| AppError::Validation(String::from("A catalog path is required by this command.")))?;
+    let bundled = opt.bundled();
+
+    // Merged shards can't be split back into their original files
+    if !opt.shards.is_empty() && opt.cmd.writes_catalog() {
+        return Err(AppError::Validation(String::from("Writing back a Catalog split across shards is not supported yet.")));
+    }
+
+    // The original bundle is reopened when saving, which can't be done with stdin
+    if bundled && catalog_path == STDIO_PATH && opt.cmd.writes_catalog() {
+        return Err(AppError::Validation(String::from("A bundled Catalog read from stdin can't be written back.")));
+    }
+
+    match opt.cmd {
+        Command::Add(args) => {
+            // Get a Catalog instance depending on the opening method
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            if let Some(seed) = opt.seed {
+                catalog.set_hash_seed(seed);
+            }
+
+            // Get the entries to add from the provided TOML, or every TOML in the provided directory
+            let entries = read_entries(&args.toml_path)?;
+
+            add_entries(&mut catalog, &entries, verbosity)?;
+
+            // Save the file to the output path
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+
+            status!(verbosity, "Added {} bundle(s) and {} prefab(s) to the Catalog.", entries.bundles.len(), entries.prefabs.len());
+        }
+        Command::Dependencies(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let internal_id = resolve_internal_id(&catalog, &args.internal_id, interactive, verbosity)?;
+
+            let (entry, summary) = summarize(&catalog, internal_id)?;
+
+            verbose!(verbosity, "Entry: {:?}", entry);
+
+            if summary.is_bundle {
+                return Err(AppError::Validation(format!("No dependency found for '{}'. Are you sure this is a prefab?", summary.internal_id)));
+            }
+
+            let direct = catalog.get_dependencies(entry).unwrap_or_default();
+            let listed = if args.recursive { catalog.dependencies_recursive(entry) } else { direct.to_vec() };
+
+            let mut bundles = 0;
+
+            for id in listed.iter() {
+                let dependency = catalog.entry_summary(*id).ok_or_else(|| {
+                    AppError::Validation(format!("A dependency of '{}' points to a missing entry ({}). Is the file corrupted?", summary.internal_id, id.0))
+                })?;
+
+                if dependency.is_bundle && direct.contains(id) {
+                    bundles += 1;
+                }
+
+                println!("Dependency found: {}", dependency.internal_id);
+            }
+
+            status!(verbosity, "{} dependencies found ({} bundles)", direct.len(), bundles);
+
+            if args.recursive {
+                status!(verbosity, "{} unique dependencies across the tree", listed.len());
+                status!(verbosity, "{} bytes of bundles in total", catalog.recursive_size(entry));
+            }
+        }
+        Command::Extract(args) => {
+            write_options.check_clobber(&args.out_path)?;
+            let already_json = extract(catalog_path, &args.out_path, opt.pretty)?;
+
+            // Status messages would end up mixed with the JSON
+            if args.out_path == STDIO_PATH {
+                return Ok(());
+            }
+
+            if already_json && !opt.pretty {
+                status!(verbosity, "The Catalog is already a JSON file, it was copied as is.");
+            } else {
+                status!(verbosity, "Catalog extracted successfully.");
+            }
+        },
+        Command::Dump(args) => {
+            // Get a Catalog instance depending on the opening method
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let internal_id = resolve_internal_id(&catalog, &args.internal_id, interactive, verbosity)?;
+
+            let entries = dump_entry(&catalog, internal_id, verbosity)?;
+
+            write_options.check_clobber(&args.out_path)?;
+            std::fs::write(args.out_path, serde_toml::to_string_pretty(&entries)?)?;
+            status!(verbosity, "Entry exported successfully.");
+        }
+        Command::Tree(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let internal_id = resolve_internal_id(&catalog, &args.internal_id, interactive, verbosity)?;
+
+            let entry_id = entry_id_of(&catalog, internal_id)?;
+
+            let mut lines = vec![entry_internal_id(&catalog, entry_id)?];
+            let mut visited = HashSet::from([entry_id]);
+            write_dependency_tree(&catalog, entry_id, "", &mut visited, &mut lines)?;
+
+            lines.iter().for_each(|line| println!("{}", line));
+
+            if let Some(entry) = catalog.get_entry(entry_id) {
+                status!(verbosity, "{} bytes of bundles in total", catalog.recursive_size(entry));
+            }
+        }
+        Command::AddDependency(args) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            if let Some(seed) = opt.seed {
+                catalog.set_hash_seed(seed);
+            }
+
+            let internal_id = resolve_internal_id(&catalog, &args.internal_id, interactive, verbosity)?;
+            let dependency = resolve_internal_id(&catalog, &args.dependency, interactive, verbosity)?;
+
+            catalog.add_dependency_to_entry(internal_id, dependency)?;
+
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+            status!(verbosity, "Dependency added successfully.");
+        }
+        Command::RemoveDependency(args) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let internal_id = resolve_internal_id(&catalog, &args.internal_id, interactive, verbosity)?;
+            let dependency = resolve_internal_id(&catalog, &args.dependency, interactive, verbosity)?;
+
+            catalog.remove_dependency_from_entry(internal_id, dependency)?;
+
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+            status!(verbosity, "Dependency removed successfully.");
+        }
+        Command::Grep(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            for (id, extra) in catalog.search_extra(&args.pattern) {
+                let internal_id = entry_internal_id(&catalog, id)?;
+
+                println!("Entry found: {}", internal_id);
+                if let Some(json_text) = extra.json_text() {
+                    verbose!(verbosity, "{}", json_text);
+                }
+            }
+        }
+        Command::FindOrphans => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let orphans = catalog.orphan_bundles();
+
+            for id in orphans.iter() {
+                let internal_id = entry_internal_id(&catalog, *id)?;
+
+                println!("Orphan found: {}", internal_id);
+            }
+
+            status!(verbosity, "{} orphan bundle(s) found.", orphans.len());
+        }
+        Command::AddKey(args) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let key = match (args.hash, args.string) {
+                (Some(hash), _) => KeyDataValue::Hash(hash),
+                (None, Some(string)) => KeyDataValue::from_string(string),
+                (None, None) => return Err(AppError::Validation(String::from("Provide either a string or a hash for the key."))),
+            };
+
+            let key_id = catalog.add_standalone_key(key)?;
+
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+            println!("Key added: {}", isize::from(key_id));
+        }
+        Command::Rewrite(args) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let count = catalog.rewrite_internal_ids(&args.from_prefix, &args.to_prefix)?;
+
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+            status!(verbosity, "Rewrote {} InternalId(s).", count);
+        }
+        Command::ValidatePaths(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let roots = args
+                .internal_ids
+                .iter()
+                .map(|query| entry_id_of(&catalog, resolve_internal_id(&catalog, query, interactive, verbosity)?))
+                .collect::<Result<Vec<EntryId>, AppError>>()?;
+
+            let bundles = validate_paths(&catalog, &roots, &args.aa_path, args.platform.as_deref())?;
+
+            for bundle in bundles.iter() {
+                match (&bundle.path, bundle.present) {
+                    (Some(path), true) => println!("Present: {}", path),
+                    (Some(path), false) => println!("Missing: {}", path),
+                    (None, _) => println!("Not in the RuntimePath: {}", bundle.internal_id),
+                }
+
+                if let (Some(path), Some(false)) = (&bundle.path, bundle.size_matches) {
+                    warn!("{} does not have the size recorded in the Catalog.", path);
+                }
+            }
+
+            let present = bundles.iter().filter(|bundle| bundle.present).count();
+            status!(verbosity, "{} of {} bundle(s) present.", present, bundles.len());
+        }
+        Command::CompareBundles(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let internal_id = resolve_internal_id(&catalog, &args.internal_id, interactive, verbosity)?;
+            let root = entry_id_of(&catalog, internal_id)?;
+
+            let bundles = compare_bundles(&catalog, root, &args.aa_path, &args.reference_path, args.platform.as_deref())?;
+
+            for (internal_id, comparison) in bundles.iter() {
+                match comparison {
+                    BundleComparison::Identical => println!("Identical: {}", internal_id),
+                    BundleComparison::Different => println!("Different: {}", internal_id),
+                    BundleComparison::Missing { first: true, second: true } => println!("Missing from both: {}", internal_id),
+                    BundleComparison::Missing { first: true, .. } => println!("Missing from {}: {}", args.aa_path, internal_id),
+                    BundleComparison::Missing { .. } => println!("Missing from {}: {}", args.reference_path, internal_id),
+                    BundleComparison::Unresolved => println!("Not in the RuntimePath: {}", internal_id),
+                }
+            }
+
+            let identical = bundles.iter().filter(|(_, comparison)| *comparison == BundleComparison::Identical).count();
+            status!(verbosity, "{} of {} bundle(s) identical.", identical, bundles.len());
+        }
+        Command::ExportIds(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let internal_ids = export_internal_ids(&catalog, args.filter.as_deref(), args.resource_type.as_deref(), !args.prefabs_only, !args.bundles_only, args.sort);
+
+            if let Some(path) = &args.out_path {
+                write_options.check_clobber(path)?;
+            }
+
+            let mut output: Box<dyn Write> = match &args.out_path {
+                Some(path) if path != STDIO_PATH => Box::new(std::io::BufWriter::new(std::fs::File::create(path)?)),
+                _ => Box::new(std::io::stdout().lock()),
+            };
+
+            for internal_id in internal_ids.iter() {
+                writeln!(output, "{}", internal_id)?;
+            }
+
+            output.flush()?;
+        }
+        Command::ValidateOffsets(args) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let mismatches = catalog.validate_offsets();
+
+            for mismatch in mismatches.iter() {
+                println!("Key {}: offset {} instead of {}", isize::from(mismatch.key), mismatch.stored, mismatch.expected);
+            }
+
+            match args.fix {
+                Some(out_path) => {
+                    catalog.fix_key_offsets();
+                    save_catalog(&mut catalog, catalog_path, &out_path, bundled, write_options)?;
+                    status!(verbosity, "Fixed {} key offset(s).", mismatches.len());
+                }
+                None if !mismatches.is_empty() => {
+                    return Err(AppError::Validation(format!("{} key offset(s) do not match the key table. Run with --fix to rewrite them.", mismatches.len())));
+                }
+                None => status!(verbosity, "Every key offset matches the key table."),
+            }
+        }
+        Command::Provider(Provider::List) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            for (index, id) in catalog.provider_ids().iter().enumerate() {
+                println!("{}\t{}", index, id);
+            }
+        }
+        Command::Provider(Provider::Add(args)) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let class_name = args.class_name.unwrap_or_else(|| args.id.clone());
+            let index = catalog.add_provider(args.id, ObjectType::new(args.assembly_name, class_name))?;
+
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+            println!("{}", index);
+            status!(verbosity, "Provider added successfully.");
+        }
+        Command::Provider(Provider::Rename(args)) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            catalog.rename_provider(&args.id, args.new_id)?;
+
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+            status!(verbosity, "Provider renamed successfully.");
+        }
+        Command::Which(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            for (entry_id, internal_id) in which(&catalog, &args.query, interactive, verbosity)? {
+                println!("{}\t{}", entry_id.0, internal_id);
+            }
+        }
+        Command::Hexdump(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let internal_id = resolve_internal_id(&catalog, &args.internal_id, interactive, verbosity)?;
+            let entry_id = entry_id_of(&catalog, internal_id)?;
+
+            for line in hexdump_entry(&catalog, entry_id, args.key, args.extra)? {
+                println!("{}", line);
+            }
+        }
+        Command::Graph(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let root = match &args.internal_id {
+                Some(query) => {
+                    let internal_id = resolve_internal_id(&catalog, query, interactive, verbosity)?;
+
+                    Some(entry_id_of(&catalog, internal_id)?)
+                }
+                None => None,
+            };
+
+            write_options.check_clobber(&args.out_path)?;
+            std::fs::write(args.out_path, dependency_graph(&catalog, root)?)?;
+            status!(verbosity, "Graph exported successfully. Render it with `dot -Tpng`.");
+        }
+        Command::Touch(args) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            if let Some(seed) = opt.seed {
+                catalog.set_hash_seed(seed);
+            }
+
+            let count = catalog.regenerate_dependency_hashes();
+
+            save_catalog(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+            status!(verbosity, "Regenerated {} dependency hash(es).", count);
+        }
+        Command::Minify(args) => {
+            let mut catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let fixed = minify(&mut catalog, catalog_path, &args.out_path, bundled, write_options)?;
+
+            status!(verbosity, "Catalog minified, {} key offset(s) fixed.", fixed);
+        }
+        Command::Completions(_) => unreachable!("completions are generated before the Catalog is opened"),
+        Command::Stats(args) => {
+            let catalog = open_catalog(catalog_path, &opt.shards, bundled)?;
+
+            let stats = CatalogStats::new(&catalog);
+
+            if args.json {
+                println!("{}", serde_json::to_string_pretty(&stats)?);
+            } else {
+                println!("Entries: {}", stats.entries);
+                println!("Extra data: {} bytes", stats.extra_data_bytes);
+
+                println!("Resource types:");
+                stats.resource_types.iter().for_each(|(name, count)| println!("  {:>6} {}", count, name));
+
+                println!("Providers:");
+                stats.providers.iter().for_each(|(name, count)| println!("  {:>6} {}", count, name));
+
+                println!("Dependencies per prefab:");
+                stats.prefab_dependencies.iter().for_each(|(dependencies, count)| println!("  {:>6} prefab(s) with {} dependencies", count, dependencies));
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// TODO: Move this to library
+#[cfg(test)]
+mod test {
+    use catalog::catalog::CatalogError;
+    use catalog::lookup::{EntryId, ExtraValue, KeyDataValue};
+    use std::collections::BTreeMap;
+    use structopt::StructOpt;
+
+    use crate::{add_entries, backup_file, compare_bundles, export_internal_ids, hexdump_entry, is_bundle_file, minify, read_entries, resolve_internal_id, save_catalog, validate_paths, which, dependency_graph, dump_entry, extract, extract_stream, rank_internal_ids, AppError, BundleComparison, CatalogEntries, CatalogStats, ExtraBundles, ExtraPrefabs, Opt, SortKey, Verbosity, WriteOptions};
+
+    #[test]
+    pub fn output_example_toml() {
+        let entries = CatalogEntries {
+            bundles: vec![
+                ExtraBundles::new(
+                    "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle",
+                    "fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle",
+                    None,
+                ),
+                ExtraBundles::new(
+                    "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle",
+                    "fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle",
+                    None,
+                ),
             ],
             prefabs: vec![
                 ExtraPrefabs {
                     internal_id: "Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab".to_string(),
                     internal_path: "Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069".to_string(),
+                    primary_key_hash: None,
                     dependencies: vec![
                         String::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/cor0af/c069/prefabs/ubody_cor0af_c069.bundle")
-                    ]
+                    ],
+                    hash: None,
                 },
                 ExtraPrefabs {
                     internal_id: "Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab".to_string(),
                     internal_path: "Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069".to_string(),
-                    dependencies: vec![]
+                    primary_key_hash: None,
+                    dependencies: vec![],
+                    hash: Some(-1337402711),
                 }
             ],
         };
 
         std::fs::write("dump.toml", serde_toml::to_string_pretty(&entries).unwrap()).unwrap()
     }
+
+    #[test]
+    pub fn exit_codes_per_error_category() {
+        let io = std::io::Error::new(std::io::ErrorKind::NotFound, "missing");
+        let json = serde_json::from_str::<u32>("{").unwrap_err();
+
+        assert_eq!(AppError::Io(std::io::Error::new(std::io::ErrorKind::NotFound, "missing")).exit_code(), 2);
+        assert_eq!(AppError::Catalog(CatalogError::Io(io)).exit_code(), 2);
+        assert_eq!(AppError::Catalog(CatalogError::Json(json)).exit_code(), 3);
+        assert_eq!(AppError::MissingInternalId(String::from("missing.prefab")).exit_code(), 4);
+        assert_eq!(AppError::Catalog(CatalogError::MissingInternalId(String::from("missing.prefab"))).exit_code(), 4);
+        assert_eq!(AppError::Catalog(CatalogError::DuplicateInternalId(String::from("body.prefab"))).exit_code(), 5);
+        assert_eq!(AppError::Validation(String::from("invalid")).exit_code(), 5);
+    }
+
+    #[test]
+    pub fn extract_plain_json() {
+        let json_path = std::env::temp_dir().join("catalog_tools_extract_plain.json");
+        let out_path = std::env::temp_dir().join("catalog_tools_extract_plain_out.json");
+        let json = "\n  {\"m_LocatorId\":\"AddressablesMainContentCatalog\"}";
+        std::fs::write(&json_path, json).unwrap();
+
+        assert!(extract(&json_path, &out_path, false).unwrap());
+        assert_eq!(std::fs::read_to_string(&out_path).unwrap(), json);
+
+        std::fs::remove_file(json_path).unwrap();
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[test]
+    pub fn extract_from_stream() {
+        let json = "{\"m_LocatorId\":\"AddressablesMainContentCatalog\"}";
+        let mut output = Vec::new();
+
+        assert!(extract_stream(std::io::Cursor::new(json), &mut output, false).unwrap());
+        assert_eq!(output, json.as_bytes());
+    }
+
+    #[test]
+    pub fn minify_pretty_catalog() {
+        let pretty_path = std::env::temp_dir().join("catalog_tools_minify_pretty.json");
+        let out_path = std::env::temp_dir().join("catalog_tools_minify.json");
+
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("body.prefab", "body", &[String::from("body.bundle")]).unwrap();
+        let pretty = catalog.to_json(true).unwrap();
+        std::fs::write(&pretty_path, &pretty).unwrap();
+
+        let mut catalog = catalog::catalog::Catalog::open(&pretty_path).unwrap();
+        let options = WriteOptions { backup: false, force: false, no_clobber: false, pretty: true, fix_hash_collisions: false };
+        assert_eq!(minify(&mut catalog, &pretty_path, &out_path, false, options).unwrap(), 0);
+
+        let minified = std::fs::read_to_string(&out_path).unwrap();
+        assert!(minified.len() < pretty.len());
+        assert!(!minified.contains('\n'));
+        assert_eq!(catalog::catalog::Catalog::from_str(&minified).unwrap(), catalog::catalog::Catalog::from_str(&pretty).unwrap());
+
+        std::fs::remove_file(pretty_path).unwrap();
+        std::fs::remove_file(out_path).unwrap();
+    }
+
+    #[test]
+    pub fn hexdump_bundle_entry() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+
+        let lines = hexdump_entry(&catalog, EntryId(0), true, true).unwrap();
+        assert_eq!(lines.len(), 12);
+        assert_eq!(lines[0], "Entry 0:");
+        // Bundles have no dependency key
+        assert!(lines[3].starts_with("00000008  ff ff ff ff "));
+        assert!(lines[3].ends_with("  dependency_key_idx"));
+        assert_eq!(lines[9], "00000000  00 0b 00 00 00 62 6f 64 79 2e 62 75 6e 64 6c 65  |.....body.bundle|  primary_key");
+        assert_eq!(lines[10], "Extra data at 0:");
+    }
+
+    #[test]
+    pub fn backup_before_write() {
+        let path = std::env::temp_dir().join("catalog_tools_backup.json");
+        let backup_path = std::env::temp_dir().join("catalog_tools_backup.json.bak");
+        let _ = std::fs::remove_file(&backup_path);
+        std::fs::write(&path, "original").unwrap();
+
+        assert_eq!(backup_file(&path, false).unwrap(), Some(backup_path.clone()));
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "original");
+
+        // An existing backup is only replaced when forced
+        std::fs::write(&path, "edited").unwrap();
+        assert!(matches!(backup_file(&path, false), Err(AppError::Validation(_))));
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "original");
+        backup_file(&path, true).unwrap();
+        assert_eq!(std::fs::read_to_string(&backup_path).unwrap(), "edited");
+
+        // Nothing to back up if the output doesn't exist yet
+        assert_eq!(backup_file(std::env::temp_dir().join("catalog_tools_backup_missing.json"), false).unwrap(), None);
+
+        std::fs::remove_file(path).unwrap();
+        std::fs::remove_file(backup_path).unwrap();
+    }
+
+    #[test]
+    pub fn no_clobber_output() {
+        let path = std::env::temp_dir().join("catalog_tools_no_clobber.json");
+        std::fs::write(&path, "previous dump").unwrap();
+
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+
+        let options = WriteOptions { backup: false, force: false, no_clobber: true, pretty: false, fix_hash_collisions: false };
+        assert!(matches!(save_catalog(&mut catalog, &path, &path, false, options), Err(AppError::Validation(_))));
+        assert_eq!(std::fs::read_to_string(&path).unwrap(), "previous dump");
+
+        // Forcing the write or leaving the flag out overwrites the file as usual
+        save_catalog(&mut catalog, &path, &path, false, WriteOptions { force: true, ..options }).unwrap();
+        assert_eq!(catalog::catalog::Catalog::open(&path).unwrap(), catalog);
+        WriteOptions { no_clobber: false, ..options }.check_clobber(&path).unwrap();
+
+        // Stdout and new files are always fine
+        options.check_clobber("-").unwrap();
+        options.check_clobber(std::env::temp_dir().join("catalog_tools_no_clobber_missing.json")).unwrap();
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    pub fn hash_collisions_fixed_on_request() {
+        let path = std::env::temp_dir().join("catalog_tools_hash_collisions.json");
+
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab_with_hash("body.prefab", "body", &[String::from("body.bundle")], 1234).unwrap();
+        catalog.add_dependency_key(KeyDataValue::Hash(1234), &[]).unwrap();
+
+        // Only warned about by default
+        let options = WriteOptions { backup: false, force: false, no_clobber: false, pretty: false, fix_hash_collisions: false };
+        save_catalog(&mut catalog, &path, &path, false, options).unwrap();
+        assert_eq!(catalog::catalog::Catalog::open(&path).unwrap().hash_collisions().len(), 1);
+
+        save_catalog(&mut catalog, &path, &path, false, WriteOptions { fix_hash_collisions: true, ..options }).unwrap();
+        assert!(catalog::catalog::Catalog::open(&path).unwrap().hash_collisions().is_empty());
+
+        std::fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    pub fn rank_fuzzy_matches() {
+        let ids = vec![
+            String::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle"),
+            String::from("Assets/Share/Addressables/Unit/Model/uBody/Cor0AF/c069/Prefabs/uBody_Cor0AF_c069.prefab"),
+            String::from("Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535.prefab"),
+            String::from("Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535_Variant.prefab"),
+        ];
+
+        let ranked = rank_internal_ids(ids, "uBody_Byl0AM_c535");
+
+        // Cor0AF doesn't match at all, and the shortest exact match comes first
+        assert_eq!(ranked.len(), 3);
+        assert!(ranked.iter().all(|id| !id.contains("Cor0AF")));
+        assert_eq!(ranked[0], "Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535.prefab");
+        assert_eq!(ranked[1], "Assets/Share/Addressables/Unit/Model/uBody/Byl0AM/c535/Prefabs/uBody_Byl0AM_c535_Variant.prefab");
+    }
+
+    #[test]
+    pub fn stats_by_type_and_dependencies() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("shared.bundle", "shared.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("body.prefab", "body", &[String::from("body.bundle"), String::from("shared.bundle")]).unwrap();
+        catalog.add_prefab("face.prefab", "face", &[String::from("shared.bundle")]).unwrap();
+        catalog.add_prefab("hair.prefab", "hair", &[String::from("shared.bundle")]).unwrap();
+
+        // Provider and resource type tables can only be filled in from the JSON
+        let mut json = serde_json::to_value(&catalog).unwrap();
+        json["m_ProviderIds"] = serde_json::json!(["AssetBundleProvider", "LegacyResourcesProvider", "BundledAssetProvider"]);
+        json["m_resourceTypes"] = serde_json::json!([{ "m_AssemblyName": "Unity.ResourceManager", "m_ClassName": "IAssetBundleResource" }]);
+        let catalog = catalog::catalog::Catalog::from_str(json.to_string()).unwrap();
+
+        let stats = CatalogStats::new(&catalog);
+
+        assert_eq!(stats.entries, 5);
+        assert_eq!(stats.extra_data_bytes, 2 * ExtraValue::default().get_size());
+        assert_eq!(stats.resource_types["IAssetBundleResource"], 2);
+        assert_eq!(stats.resource_types["<unknown>"], 3);
+        assert_eq!(stats.providers["AssetBundleProvider"], 2);
+        assert_eq!(stats.providers["BundledAssetProvider"], 3);
+        assert_eq!(stats.prefab_dependencies, BTreeMap::from([(1, 2), (2, 1)]));
+    }
+
+    #[test]
+    pub fn dump_then_add_bundle() {
+        let bundle = "{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle";
+        let extra = ExtraValue::new(
+            "Unity.ResourceManager, Version=0.0.0.0, Culture=neutral, PublicKeyToken=null",
+            "UnityEngine.ResourceManagement.ResourceProviders.AssetBundleRequestOptions",
+            "{\"m_Hash\":\"\",\"m_Crc\":1234,\"m_BundleName\":\"ubody_byl0am_c535\",\"m_BundleSize\":2048}",
+        );
+
+        let mut original = catalog::catalog::Catalog::default();
+        original.add_bundle(bundle, "fe_assets_unit/model/ubody/byl0am/c535/prefabs/ubody_byl0am_c535.bundle", extra.clone()).unwrap();
+
+        let toml = serde_toml::to_string_pretty(&dump_entry(&original, original.get_internal_id_index(bundle).unwrap(), Verbosity::Quiet).unwrap()).unwrap();
+        let entries: CatalogEntries = serde_toml::from_str(&toml).unwrap();
+
+        let mut copy = catalog::catalog::Catalog::default();
+        add_entries(&mut copy, &entries, Verbosity::Quiet).unwrap();
+
+        let entry = copy.get_entry_by_internal_id(copy.get_internal_id_index(bundle).unwrap()).unwrap();
+        assert_eq!(copy.extra_of(entry), Some(&extra));
+        assert_eq!(copy.extra_data_size(), original.extra_data_size());
+    }
+
+    #[test]
+    pub fn graph_of_prefab() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("shared.bundle", "shared.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle("unrelated.bundle", "unrelated.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("body.prefab", "body", &[String::from("body.bundle"), String::from("shared.bundle")]).unwrap();
+
+        let graph = dependency_graph(&catalog, Some(EntryId(3))).unwrap();
+
+        assert!(graph.starts_with("digraph catalog {"));
+        assert!(graph.contains("e3 [label=\"body.prefab\", shape=ellipse];"));
+        assert!(graph.contains("e1 [label=\"body.bundle\", shape=box"));
+        assert!(graph.contains("e3 -> e1;"));
+        assert!(graph.contains("e3 -> e0;"));
+        // Only the closure of the prefab is exported
+        assert!(!graph.contains("unrelated.bundle"));
+
+        assert!(dependency_graph(&catalog, None).unwrap().contains("unrelated.bundle"));
+    }
+
+    #[test]
+    pub fn add_from_directory() {
+        let dir = camino::Utf8PathBuf::from_path_buf(std::env::temp_dir().join("catalog_tools_add_directory")).unwrap();
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir(&dir).unwrap();
+
+        // The prefab comes first in name order, but depends on the bundle of the other file
+        std::fs::write(dir.join("a_prefab.toml"), r#"
+[[prefabs]]
+internal_id = "body.prefab"
+internal_path = "body"
+dependencies = ["body.bundle"]
+"#).unwrap();
+        std::fs::write(dir.join("b_bundle.toml"), r#"
+[[bundles]]
+internal_id = "body.bundle"
+internal_path = "body.bundle"
+assembly_name = "Unity.ResourceManager"
+class_name = "UnityEngine.ResourceManagement.ResourceProviders.AssetBundleRequestOptions"
+json_text = "{}"
+"#).unwrap();
+        std::fs::write(dir.join("notes.txt"), "not a TOML").unwrap();
+
+        let entries = read_entries(&dir).unwrap();
+        assert_eq!((entries.bundles.len(), entries.prefabs.len()), (1, 1));
+
+        let mut catalog = catalog::catalog::Catalog::default();
+        add_entries(&mut catalog, &entries, Verbosity::Quiet).unwrap();
+
+        let prefab = catalog.get_entry_by_internal_id(catalog.get_internal_id_index("body.prefab").unwrap()).unwrap();
+        assert_eq!(catalog.dependencies_recursive(prefab), vec![EntryId(0)]);
+
+        std::fs::remove_dir_all(dir).unwrap();
+    }
+
+    #[test]
+    pub fn detect_bundled_catalog() {
+        let json_path = std::env::temp_dir().join("catalog_tools_detect.json");
+        let bundle_path = std::env::temp_dir().join("catalog_tools_detect.bundle");
+        std::fs::write(&json_path, "{\"m_LocatorId\":\"AddressablesMainContentCatalog\"}").unwrap();
+        std::fs::write(&bundle_path, b"UnityFS\0\0\0\0\x085.x.x\x002020.3.18f1\0").unwrap();
+
+        assert!(!is_bundle_file(&json_path).unwrap());
+        assert!(is_bundle_file(&bundle_path).unwrap());
+
+        let bundled = |args: &[&str], path: &std::path::Path| {
+            let path = path.to_str().unwrap();
+            Opt::from_iter(["catalog_tools"].iter().chain(args).chain(&[path, "find-orphans"])).bundled()
+        };
+
+        assert!(!bundled(&[], &json_path));
+        assert!(bundled(&[], &bundle_path));
+        assert!(bundled(&["-b"], &json_path));
+        assert!(bundled(&["--input-format", "bundle"], &json_path));
+        assert!(!bundled(&["--input-format", "json"], &bundle_path));
+        // An explicit format wins over --bundled
+        assert!(!bundled(&["-b", "--input-format", "json"], &bundle_path));
+        assert!(bundled(&["--input-format", "auto"], &bundle_path));
+
+        std::fs::remove_file(json_path).unwrap();
+        std::fs::remove_file(bundle_path).unwrap();
+    }
+
+    #[test]
+    pub fn errors_name_the_internal_id() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+
+        let missing = resolve_internal_id(&catalog, "qqqq.prefab", true, Verbosity::Quiet).unwrap_err();
+        assert!(missing.to_string().contains("'qqqq.prefab'"));
+
+        let duplicate = AppError::from(catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap_err());
+        assert!(duplicate.to_string().contains("'body.bundle'"));
+    }
+
+    #[test]
+    pub fn errors_as_json() {
+        let io = AppError::from(std::io::Error::new(std::io::ErrorKind::NotFound, "catalog.json"));
+        assert_eq!(io.to_json(), serde_json::json!({
+            "error": "Io",
+            "message": "An error happened while accessing a file: catalog.json",
+            "context": { "kind": "NotFound" },
+        }));
+
+        let missing = AppError::MissingInternalId(String::from("body.prefab"));
+        assert_eq!(missing.to_json()["error"], "MissingInternalId");
+        assert_eq!(missing.to_json()["message"], missing.to_string());
+        assert_eq!(missing.to_json()["context"], serde_json::json!({ "internal_id": "body.prefab" }));
+
+        let missing = AppError::Catalog(CatalogError::MissingInternalId(String::from("body.prefab")));
+        assert_eq!(missing.to_json()["error"], "MissingInternalId");
+        assert!(missing.to_string().contains("body.prefab"));
+        assert_eq!(missing.to_json()["context"], serde_json::json!({ "internal_id": "body.prefab" }));
+    }
+
+    #[test]
+    pub fn validate_bundle_paths() {
+        let aa = camino::Utf8PathBuf::from_path_buf(std::env::temp_dir().join("catalog_tools_validate_paths")).unwrap();
+        let _ = std::fs::remove_dir_all(&aa);
+        std::fs::create_dir_all(aa.join("Switch/fe_assets_unit")).unwrap();
+        std::fs::write(aa.join("Switch/fe_assets_unit/body.bundle"), b"UnityFS").unwrap();
+        std::fs::write(aa.join("Switch/fe_assets_unit/head.bundle"), b"UnityFS").unwrap();
+
+        let sized = |size: u64| ExtraValue::new(String::new(), String::new(), format!("{{\"m_BundleSize\":{}}}", size));
+
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/body.bundle", "fe_assets_unit/body.bundle", sized(7)).unwrap();
+        catalog.add_bundle("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/head.bundle", "fe_assets_unit/head.bundle", sized(1024)).unwrap();
+        catalog.add_bundle("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/shared.bundle", "fe_assets_unit/shared.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("body.prefab", "body", &[
+            String::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/body.bundle"),
+            String::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/head.bundle"),
+            String::from("{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/shared.bundle"),
+        ]).unwrap();
+
+        let bundles = validate_paths(&catalog, &[EntryId(3)], &aa, None).unwrap();
+        let report: Vec<(Option<camino::Utf8PathBuf>, bool, Option<bool>)> = bundles.into_iter().map(|bundle| (bundle.path, bundle.present, bundle.size_matches)).collect();
+
+        assert_eq!(report, vec![
+            (Some(aa.join("Switch/fe_assets_unit/body.bundle")), true, Some(true)),
+            (Some(aa.join("Switch/fe_assets_unit/head.bundle")), true, Some(false)),
+            (Some(aa.join("Switch/fe_assets_unit/shared.bundle")), false, None),
+        ]);
+
+        std::fs::remove_dir_all(aa).unwrap();
+    }
+
+    #[test]
+    pub fn export_filtered_ids() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("fe_assets_unit/body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle("fe_assets_unit/shared.bundle", "shared.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("Assets/Unit/body.prefab", "body", &[String::from("fe_assets_unit/body.bundle")]).unwrap();
+        catalog.add_prefab("Assets/Unit/head.prefab", "head", &[String::from("fe_assets_unit/shared.bundle")]).unwrap();
+
+        assert_eq!(export_internal_ids(&catalog, Some("body"), None, true, true, None), vec!["fe_assets_unit/body.bundle", "Assets/Unit/body.prefab"]);
+        assert_eq!(export_internal_ids(&catalog, None, None, false, true, None), vec!["Assets/Unit/body.prefab", "Assets/Unit/head.prefab"]);
+        assert_eq!(export_internal_ids(&catalog, Some("shared"), None, true, false, None), vec!["fe_assets_unit/shared.bundle"]);
+        assert!(export_internal_ids(&catalog, Some("shared"), None, false, true, None).is_empty());
+    }
+
+    #[test]
+    pub fn ambiguous_query_without_prompt() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("fe_assets_unit/body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle("fe_assets_unit/shared.bundle", "shared.bundle", ExtraValue::default()).unwrap();
+
+        let err = resolve_internal_id(&catalog, "bundle", false, Verbosity::Quiet).unwrap_err();
+        assert!(matches!(&err, AppError::AmbiguousInternalId(query, matches) if query == "bundle" && matches.len() == 2));
+        assert!(err.to_string().contains("fe_assets_unit/shared.bundle"));
+        assert_eq!(err.exit_code(), 4);
+
+        // A single match is picked without asking
+        let internal_id = resolve_internal_id(&catalog, "shared", false, Verbosity::Quiet).unwrap();
+        assert_eq!(catalog.get_internal_id_from_index(internal_id).as_deref(), Some("fe_assets_unit/shared.bundle"));
+    }
+
+    #[test]
+    pub fn dump_hash_primary_key() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        let primary_key = catalog.get_entry(EntryId(0)).unwrap().primary_key;
+        *catalog.get_key_mut(primary_key).unwrap() = KeyDataValue::Hash(1234);
+
+        let entries = dump_entry(&catalog, catalog.get_internal_id_index("body.bundle").unwrap(), Verbosity::Quiet).unwrap();
+        let toml = serde_toml::to_string_pretty(&entries).unwrap();
+        assert!(toml.contains("primary_key_hash = 1234"));
+
+        // Adding an entry keyed by a hash isn't supported
+        let entries: CatalogEntries = serde_toml::from_str(&toml).unwrap();
+        let mut copy = catalog::catalog::Catalog::default();
+        assert!(matches!(add_entries(&mut copy, &entries, Verbosity::Quiet), Err(AppError::Validation(_))));
+        assert_eq!(copy.entry_count(), 0);
+    }
+
+    #[test]
+    pub fn which_internal_ids() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("fe_assets_unit/body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle("fe_assets_unit/shared.bundle", "shared.bundle", ExtraValue::default()).unwrap();
+
+        assert_eq!(which(&catalog, "shared", false, Verbosity::Quiet).unwrap(), vec![(EntryId(1), String::from("fe_assets_unit/shared.bundle"))]);
+        assert_eq!(which(&catalog, "fe_assets_unit/body.bundle", true, Verbosity::Quiet).unwrap(), vec![(EntryId(0), String::from("fe_assets_unit/body.bundle"))]);
+
+        // Every match is listed instead of prompting
+        let matches = which(&catalog, "bundle", false, Verbosity::Quiet).unwrap();
+        assert_eq!(matches.len(), 2);
+        assert!(matches.contains(&(EntryId(0), String::from("fe_assets_unit/body.bundle"))));
+
+        assert!(matches!(which(&catalog, "qqqq", false, Verbosity::Quiet), Err(AppError::MissingInternalId(_))));
+    }
+
+    #[test]
+    pub fn sort_exported_ids() {
+        let mut catalog = catalog::catalog::Catalog::default();
+        catalog.add_bundle("fe_assets_unit/shared.bundle", "shared.bundle", ExtraValue::default()).unwrap();
+        catalog.add_bundle("fe_assets_unit/body.bundle", "body.bundle", ExtraValue::default()).unwrap();
+        catalog.add_prefab("Assets/Unit/head.prefab", "head", &[String::from("fe_assets_unit/shared.bundle"), String::from("fe_assets_unit/body.bundle")]).unwrap();
+        catalog.add_prefab("Assets/Unit/body.prefab", "body", &[String::from("fe_assets_unit/body.bundle")]).unwrap();
+
+        // Bundles use the first resource type and prefabs the fifth
+        let mut json: serde_json::Value = serde_json::from_str(&catalog.to_json(false).unwrap()).unwrap();
+        json["m_resourceTypes"] = serde_json::json!([
+            { "m_AssemblyName": "Unity.ResourceManager", "m_ClassName": "UnityEngine.ResourceManagement.ResourceProviders.IAssetBundleResource" },
+            { "m_AssemblyName": "", "m_ClassName": "" },
+            { "m_AssemblyName": "", "m_ClassName": "" },
+            { "m_AssemblyName": "", "m_ClassName": "" },
+            { "m_AssemblyName": "UnityEngine.CoreModule", "m_ClassName": "UnityEngine.GameObject" },
+        ]);
+        let catalog = catalog::catalog::Catalog::from_str(json.to_string()).unwrap();
+
+        let sorted = |sort| export_internal_ids(&catalog, None, None, true, true, Some(sort));
+
+        assert_eq!(sorted(SortKey::Id), vec!["Assets/Unit/body.prefab", "Assets/Unit/head.prefab", "fe_assets_unit/body.bundle", "fe_assets_unit/shared.bundle"]);
+        assert_eq!(sorted(SortKey::Type), vec!["Assets/Unit/head.prefab", "Assets/Unit/body.prefab", "fe_assets_unit/shared.bundle", "fe_assets_unit/body.bundle"]);
+        assert_eq!(sorted(SortKey::Provider), vec!["fe_assets_unit/shared.bundle", "fe_assets_unit/body.bundle", "Assets/Unit/head.prefab", "Assets/Unit/body.prefab"]);
+        assert_eq!(sorted(SortKey::Deps), vec!["fe_assets_unit/shared.bundle", "fe_assets_unit/body.bundle", "Assets/Unit/body.prefab", "Assets/Unit/head.prefab"]);
+
+        assert_eq!(export_internal_ids(&catalog, None, Some("UnityEngine.GameObject"), true, true, None), vec!["Assets/Unit/head.prefab", "Assets/Unit/body.prefab"]);
+
+        // The Catalog keeps its order
+        assert_eq!(export_internal_ids(&catalog, None, None, true, true, None), catalog.get_internal_ids());
+    }
+
+    #[test]
+    pub fn compare_two_aa_directories() {
+        let root = camino::Utf8PathBuf::from_path_buf(std::env::temp_dir().join("catalog_tools_compare_bundles")).unwrap();
+        let _ = std::fs::remove_dir_all(&root);
+        let (aa, reference) = (root.join("aa"), root.join("reference"));
+
+        for dir in [&aa, &reference] {
+            std::fs::create_dir_all(dir.join("Switch/fe_assets_unit")).unwrap();
+            std::fs::write(dir.join("Switch/fe_assets_unit/shared.bundle"), b"UnityFS shared").unwrap();
+        }
+        std::fs::write(aa.join("Switch/fe_assets_unit/body.bundle"), b"UnityFS body v2").unwrap();
+        std::fs::write(reference.join("Switch/fe_assets_unit/body.bundle"), b"UnityFS body v1").unwrap();
+        std::fs::write(reference.join("Switch/fe_assets_unit/head.bundle"), b"UnityFS head").unwrap();
+
+        let mut catalog = catalog::catalog::Catalog::default();
+        let bundles: Vec<String> = ["body", "shared", "head"]
+            .iter()
+            .map(|name| format!("{{UnityEngine.AddressableAssets.Addressables.RuntimePath}}/Switch/fe_assets_unit/{}.bundle", name))
+            .collect();
+        for bundle in bundles.iter() {
+            catalog.add_bundle(bundle.as_str(), bundle.as_str(), ExtraValue::default()).unwrap();
+        }
+        catalog.add_prefab("body.prefab", "body", &bundles).unwrap();
+
+        let comparisons = compare_bundles(&catalog, EntryId(3), &aa, &reference, None).unwrap();
+        let comparisons: Vec<BundleComparison> = comparisons.into_iter().map(|(_, comparison)| comparison).collect();
+
+        assert_eq!(comparisons, vec![
+            BundleComparison::Different,
+            BundleComparison::Identical,
+            BundleComparison::Missing { first: true, second: false },
+        ]);
+
+        std::fs::remove_dir_all(root).unwrap();
+    }
 }
@@ -0,0 +1,81 @@
+use std::process::{Command, Output};
+
+fn fixture_path(name: &str) -> std::path::PathBuf {
+    std::path::Path::new(env!("CARGO_MANIFEST_DIR")).join("catalog/tests/fixtures").join(name)
+}
+
+/// Run the tool on a fixture Catalog without prompting
+fn run_on(fixture: &str, args: &[&str]) -> Output {
+    Command::new(env!("CARGO_BIN_EXE_catalog_tools"))
+        .arg("--no-interactive")
+        .arg(fixture_path(fixture))
+        .args(args)
+        .output()
+        .unwrap()
+}
+
+fn run(args: &[&str]) -> Output {
+    run_on("catalog.json", args)
+}
+
+fn stdout_lines(output: &Output) -> Vec<String> {
+    String::from_utf8_lossy(&output.stdout).lines().map(String::from).collect()
+}
+
+#[test]
+pub fn list_dependencies() {
+    let output = run(&["-q", "dependencies", "Assets/Unit/uBody_A.prefab"]);
+    assert!(output.status.success());
+
+    assert_eq!(stdout_lines(&output), vec![
+        "Dependency found: {UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/common/shared.bundle",
+        "Dependency found: {UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/ubody/a/a.bundle",
+    ]);
+}
+
+#[test]
+pub fn export_prefab_ids() {
+    let output = run(&["export-ids", "--prefabs-only"]);
+    assert!(output.status.success());
+
+    assert_eq!(stdout_lines(&output), vec!["Assets/Unit/uBody_A.prefab", "Assets/Unit/uBody_B.prefab"]);
+}
+
+#[test]
+pub fn list_dependencies_of_bundled_catalog() {
+    let output = run_on("catalog.bundle", &["-q", "dependencies", "Assets/Unit/uBody_A.prefab"]);
+    assert!(output.status.success());
+
+    assert_eq!(stdout_lines(&output), stdout_lines(&run(&["-q", "dependencies", "Assets/Unit/uBody_A.prefab"])));
+}
+
+#[test]
+pub fn missing_internal_id() {
+    let output = run(&["tree", "Assets/Unit/uBody_Z.prefab"]);
+
+    assert_eq!(output.status.code(), Some(4));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("uBody_Z"));
+}
+
+#[test]
+pub fn completions_without_catalog() {
+    let output = Command::new(env!("CARGO_BIN_EXE_catalog_tools")).args(["completions", "bash"]).output().unwrap();
+    assert!(output.status.success());
+
+    assert!(String::from_utf8_lossy(&output.stdout).contains("catalog_tools"));
+}
+
+#[test]
+pub fn duplicate_add_fails_with_a_message() {
+    let toml_path = std::env::temp_dir().join("catalog_tools_cli_duplicate.toml");
+    let out_path = std::env::temp_dir().join("catalog_tools_cli_duplicate.json");
+    std::fs::write(&toml_path, "[[bundles]]\ninternal_id = \"{UnityEngine.AddressableAssets.Addressables.RuntimePath}/Switch/fe_assets_unit/model/common/shared.bundle\"\ninternal_path = \"fe_assets_unit/model/common/shared.bundle\"\njson_text = \"{}\"\n").unwrap();
+
+    let output = run(&["add", out_path.to_str().unwrap(), toml_path.to_str().unwrap()]);
+
+    assert_eq!(output.status.code(), Some(5));
+    assert!(String::from_utf8_lossy(&output.stderr).contains("shared.bundle"));
+    assert!(!out_path.exists());
+
+    std::fs::remove_file(toml_path).unwrap();
+}